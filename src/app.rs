@@ -0,0 +1,51 @@
+use amethyst::{
+    audio::AudioBundle,
+    core::transform::TransformBundle,
+    input::{InputBundle, SdlEventsSystemDesc, StringBindings},
+    prelude::*,
+    renderer::{
+        plugins::{RenderFlat2D, RenderToWindow},
+        types::DefaultBackend,
+        RenderingBundle,
+    },
+    tiles::{MortonEncoder, RenderTiles2D},
+    ui::{RenderUi, UiBundle},
+    utils::application_root_dir,
+};
+
+use crate::game::{load_bindings, BotRegistry, SnakeGameTile};
+use crate::states::SplashState;
+
+// Starts the game, with any bots from other crates ready for the computer snakes to play as.
+pub fn run(bots: BotRegistry) -> amethyst::Result<()> {
+    amethyst::start_logger(Default::default());
+
+    let app_root = application_root_dir()?;
+
+    let resources_dir = app_root.join("resources\\");
+
+    let config_dir = app_root.join("config");
+    let display_config_path = config_dir.join("display.ron");
+
+    let game_data = GameDataBuilder::default()
+        .with_bundle(
+            RenderingBundle::<DefaultBackend>::new()
+                .with_plugin(
+                    RenderToWindow::from_config_path(display_config_path)?
+                        .with_clear([0.0, 0.0, 0.0, 1.0]),
+                )
+                .with_plugin(RenderFlat2D::default())
+                .with_plugin(RenderTiles2D::<SnakeGameTile, MortonEncoder>::default())
+                .with_plugin(RenderUi::default()),
+        )?
+        .with_bundle(InputBundle::<StringBindings>::new().with_bindings(load_bindings()))?
+        .with_bundle(TransformBundle::new())?
+        .with_bundle(UiBundle::<StringBindings>::new())?
+        .with_bundle(AudioBundle::default())?
+        .with_thread_local_desc(SdlEventsSystemDesc::<StringBindings>::default());
+
+    let mut game = Application::new(resources_dir, SplashState::with_bots(bots), game_data)?;
+    game.run();
+
+    Ok(())
+}
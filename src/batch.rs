@@ -0,0 +1,434 @@
+use serde::Serialize;
+
+use std::{collections::BTreeMap, env, path::PathBuf, process};
+
+use crate::game::{
+    defines::*, play_headless, BoardConfig, BotRegistry, Controller, Difficulty, GameMode,
+    GameRecord, GameSeed, Level, Pilot, Simulation, SnakeController, WallMode, WinCondition,
+};
+
+const USAGE: &str = "Plays batches of snake games with no window and reports how they went.
+
+Usage: simulate [options]
+
+  --seeds <list>        Seeds to play, like 0..1000 or 1,5,9 (default 0..100)
+  --bot <name>          easy, normal, hard, autopilot or a registered bot (default hard)
+  --width <cells>       Board width (default 40)
+  --height <cells>      Board height (default 40)
+  --walls <mode>        solid or wrap (default solid)
+  --level <file>        Play a level file instead of an open board
+  --mode <file>         Game mode file (default config/game_mode.ron)
+  --opponents <count>   Computer snakes playing against the bot (default 0)
+  --difficulty <name>   How hard the computer snakes play (default normal)
+  --win <condition>     last or score, when there are opponents (default last)
+  --max-ticks <ticks>   Cut games off after this many ticks (default 100000)
+  --format <format>     csv or json (default csv)";
+
+// Every game's record is kept for the summary, so batches can't go on forever.
+const MAX_SEEDS: usize = 10_000_000;
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Format {
+    Csv,
+    Json,
+}
+
+struct Options {
+    seeds: Vec<u64>,
+    bot: Controller,
+    board: BoardConfig,
+    level: Option<String>,
+    mode: Option<String>,
+    opponents: usize,
+    difficulty: Difficulty,
+    win_condition: WinCondition,
+    max_ticks: u32,
+    format: Format,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            seeds: (0..100).collect(),
+            bot: Controller::Ai(Difficulty::Hard),
+            board: BoardConfig::default(),
+            level: None,
+            mode: None,
+            opponents: 0,
+            difficulty: Difficulty::default(),
+            win_condition: WinCondition::default(),
+            max_ticks: 100_000,
+            format: Format::Csv,
+        }
+    }
+}
+
+// Totals over every game in the batch.
+#[derive(Serialize)]
+struct Summary {
+    games: usize,
+    mean_score: f64,
+    min_score: u32,
+    max_score: u32,
+    mean_length: f64,
+    max_length: usize,
+    mean_ticks: f64,
+    max_ticks: u32,
+    //How many games ended each way
+    deaths: BTreeMap<String, usize>,
+}
+
+impl Summary {
+    fn new(records: &[GameRecord]) -> Self {
+        let mean = |total: f64| {
+            if records.is_empty() {
+                0.0
+            } else {
+                total / records.len() as f64
+            }
+        };
+        let mut deaths = BTreeMap::new();
+        for record in records {
+            *deaths.entry(format!("{:?}", record.death)).or_insert(0) += 1;
+        }
+        Summary {
+            games: records.len(),
+            mean_score: mean(records.iter().map(|r| r.score as f64).sum()),
+            min_score: records.iter().map(|r| r.score).min().unwrap_or(0),
+            max_score: records.iter().map(|r| r.score).max().unwrap_or(0),
+            mean_length: mean(records.iter().map(|r| r.length as f64).sum()),
+            max_length: records.iter().map(|r| r.length).max().unwrap_or(0),
+            mean_ticks: mean(records.iter().map(|r| r.ticks as f64).sum()),
+            max_ticks: records.iter().map(|r| r.ticks).max().unwrap_or(0),
+            deaths,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    games: &'a [GameRecord],
+    summary: Summary,
+}
+
+// Runs the simulate binary's batch from the command line, with any bots from other crates
+// ready to be picked with --bot.
+pub fn simulate(bots: BotRegistry) {
+    let options = match parse_args(env::args().skip(1), &bots) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let level = match &options.level {
+        Some(path) => Level::load(path).unwrap_or_else(|e| {
+            eprintln!("Could not load {}: {}", path, e);
+            process::exit(1);
+        }),
+        None => Level::open(&options.board),
+    };
+    if let Err(e) = level.spawn_points(1 + options.opponents) {
+        eprintln!("Could not play {}: {}", level.name, e);
+        process::exit(1);
+    }
+    let mode = load_game_mode(options.mode.as_deref());
+
+    let records: Vec<GameRecord> = options
+        .seeds
+        .iter()
+        .map(|&seed| play(&options, &level, &mode, &bots, GameSeed(seed)))
+        .collect();
+    let summary = Summary::new(&records);
+
+    match options.format {
+        Format::Csv => {
+            println!("seed,score,length,ticks,death");
+            for record in records.iter() {
+                println!(
+                    "{},{},{},{},{:?}",
+                    record.seed, record.score, record.length, record.ticks, record.death
+                );
+            }
+            //Kept off stdout so the rows can be piped straight into something else
+            eprintln!(
+                "{} games: score {:.1} mean ({}-{}), length {:.1} mean ({} max), ticks {:.1} mean ({} max)",
+                summary.games,
+                summary.mean_score,
+                summary.min_score,
+                summary.max_score,
+                summary.mean_length,
+                summary.max_length,
+                summary.mean_ticks,
+                summary.max_ticks
+            );
+            for (death, count) in summary.deaths.iter() {
+                eprintln!("  {}: {}", death, count);
+            }
+        }
+        Format::Json => {
+            let report = Report {
+                games: &records,
+                summary,
+            };
+            match serde_json::to_string_pretty(&report) {
+                Ok(json) => println!("{}", json),
+                Err(e) => {
+                    eprintln!("Could not write the report: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+// One game with the bot as the first snake and any computer opponents after it.
+fn play(
+    options: &Options,
+    level: &Level,
+    mode: &GameMode,
+    bots: &BotRegistry,
+    seed: GameSeed,
+) -> GameRecord {
+    let (mut simulation, mut snakes) = Simulation::multiplayer(
+        level,
+        options.board.wall_mode,
+        mode.clone(),
+        seed,
+        1 + options.opponents,
+        options.win_condition,
+    );
+    let controllers: Vec<Controller> = std::iter::once(options.bot.clone())
+        .chain((0..options.opponents).map(|_| Controller::Ai(options.difficulty)))
+        .collect();
+    let mut pilots: Vec<Box<dyn SnakeController>> = controllers
+        .iter()
+        .filter_map(|controller| controller.pilot(&controllers, bots))
+        .map(Pilot::into_controller)
+        .collect();
+
+    play_headless(&mut simulation, &mut snakes, &mut pilots, options.max_ticks);
+    GameRecord::new(seed, &simulation, &snakes[0])
+}
+
+fn load_game_mode(path: Option<&str>) -> GameMode {
+    let path = match path {
+        Some(path) => path.into(),
+        None => root_dir().join("config").join("game_mode.ron"),
+    };
+    GameMode::load(&path).unwrap_or_else(|e| {
+        eprintln!("Using the default game mode: {}", e);
+        GameMode::default()
+    })
+}
+
+// Where the config folder is, next to the manifest under cargo or else next to the binary.
+fn root_dir() -> PathBuf {
+    env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .or_else(|| Some(env::current_exe().ok()?.parent()?.to_path_buf()))
+        .unwrap_or_default()
+}
+
+fn parse_args<I: Iterator<Item = String>>(
+    mut args: I,
+    bots: &BotRegistry,
+) -> Result<Options, String> {
+    let mut options = Options::default();
+    //Levels come with their own size, so a size given alongside one would be ignored
+    let mut sized = false;
+    while let Some(flag) = args.next() {
+        if flag == "--help" || flag == "-h" {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+        let number = |value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("{} takes a number, not {}", flag, value))
+        };
+
+        match flag.as_str() {
+            "--seeds" => options.seeds = parse_seeds(&value)?,
+            "--bot" => {
+                options.bot = match value.as_str() {
+                    "autopilot" => Controller::Autopilot(0),
+                    name if bots.names().iter().any(|bot| bot == name) => {
+                        Controller::Bot(name.to_string())
+                    }
+                    name => Controller::Ai(parse_difficulty(name)?),
+                }
+            }
+            "--width" => {
+                options.board.width = number(&value)?;
+                sized = true;
+            }
+            "--height" => {
+                options.board.height = number(&value)?;
+                sized = true;
+            }
+            "--walls" => {
+                options.board.wall_mode = match value.as_str() {
+                    "solid" => WallMode::Solid,
+                    "wrap" => WallMode::Wrap,
+                    _ => return Err(format!("Unknown wall mode {}", value)),
+                }
+            }
+            "--level" => options.level = Some(value),
+            "--mode" => options.mode = Some(value),
+            "--opponents" => options.opponents = number(&value)? as usize,
+            "--difficulty" => options.difficulty = parse_difficulty(&value)?,
+            "--win" => {
+                options.win_condition = match value.as_str() {
+                    "last" => WinCondition::LastSnakeStanding,
+                    "score" => WinCondition::HighestScore,
+                    _ => return Err(format!("Unknown win condition {}", value)),
+                }
+            }
+            "--max-ticks" => options.max_ticks = number(&value)?,
+            "--format" => {
+                options.format = match value.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    _ => return Err(format!("Unknown format {}", value)),
+                }
+            }
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    if sized && options.level.is_some() {
+        return Err("--width and --height can't be used with --level".to_string());
+    }
+    let sizes = MIN_BOARD_SIZE..=MAX_BOARD_SIZE;
+    if !sizes.contains(&options.board.width) || !sizes.contains(&options.board.height) {
+        return Err(format!(
+            "Boards go from {} to {} cells across",
+            MIN_BOARD_SIZE, MAX_BOARD_SIZE
+        ));
+    }
+    if options.opponents >= MAX_PLAYERS {
+        return Err(format!(
+            "There's only room for {} opponents",
+            MAX_PLAYERS - 1
+        ));
+    }
+    Ok(options)
+}
+
+// Comma separated seeds, each one a number or a half open range like 0..100, up to
+// MAX_SEEDS of them in all.
+fn parse_seeds(list: &str) -> Result<Vec<u64>, String> {
+    let seed = |text: &str| {
+        text.trim()
+            .parse::<u64>()
+            .map_err(|_| format!("{} is not a seed", text))
+    };
+    let mut seeds = Vec::new();
+    for part in list.split(',') {
+        let mut ends = part.splitn(2, "..");
+        let start = seed(ends.next().unwrap_or(""))?;
+        match ends.next() {
+            Some(end) => {
+                let end = seed(end)?;
+                if end < start {
+                    return Err(format!("{} runs backwards", part));
+                }
+                if end - start > (MAX_SEEDS - seeds.len()) as u64 {
+                    return Err(format!("There's a limit of {} seeds", MAX_SEEDS));
+                }
+                seeds.extend(start..end);
+            }
+            None if seeds.len() < MAX_SEEDS => seeds.push(start),
+            None => return Err(format!("There's a limit of {} seeds", MAX_SEEDS)),
+        }
+    }
+    Ok(seeds)
+}
+
+fn parse_difficulty(name: &str) -> Result<Difficulty, String> {
+    match name {
+        "easy" => Ok(Difficulty::Easy),
+        "normal" => Ok(Difficulty::Normal),
+        "hard" => Ok(Difficulty::Hard),
+        _ => Err(format!("Unknown difficulty {}", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BoardView, Direction, GameState};
+
+    struct Straight;
+
+    impl SnakeController for Straight {
+        fn steer(&mut self, _view: &BoardView) -> Option<Direction> {
+            None
+        }
+    }
+
+    fn parse(args: &str) -> Result<Options, String> {
+        let mut bots = BotRegistry::default();
+        bots.register("straight", || Box::new(Straight));
+        parse_args(args.split_whitespace().map(String::from), &bots)
+    }
+
+    #[test]
+    fn seeds_take_numbers_and_ranges() {
+        assert_eq!(parse_seeds("3"), Ok(vec![3]));
+        assert_eq!(parse_seeds("0..3,7"), Ok(vec![0, 1, 2, 7]));
+        assert_eq!(parse_seeds("5..5"), Ok(vec![]));
+    }
+
+    #[test]
+    fn bad_seeds_are_errors() {
+        assert!(parse_seeds("5..3").is_err());
+        assert!(parse_seeds("x").is_err());
+        assert!(parse_seeds("1..").is_err());
+        assert!(parse_seeds("0..18446744073709551615").is_err());
+    }
+
+    #[test]
+    fn options_are_read() {
+        let options =
+            parse("--seeds 1,2 --bot autopilot --width 20 --walls wrap --opponents 2").unwrap();
+        assert_eq!(options.seeds, vec![1, 2]);
+        assert_eq!(options.bot, Controller::Autopilot(0));
+        assert_eq!(options.board.width, 20);
+        assert_eq!(options.board.wall_mode, WallMode::Wrap);
+        assert_eq!(options.opponents, 2);
+    }
+
+    #[test]
+    fn registered_bots_can_play() {
+        let options = parse("--bot straight").unwrap();
+        assert_eq!(options.bot, Controller::Bot("straight".to_string()));
+        assert!(parse("--bot crooked").is_err());
+    }
+
+    #[test]
+    fn bad_options_are_errors() {
+        assert!(parse("--level levels/maze.ron --width 20").is_err());
+        assert!(parse("--height 20 --level levels/maze.ron").is_err());
+        assert!(parse("--width 5").is_err());
+        assert!(parse(&format!("--opponents {}", MAX_PLAYERS)).is_err());
+        assert!(parse("--seeds").is_err());
+        assert!(parse("--colour red").is_err());
+    }
+
+    #[test]
+    fn registered_bots_play_headless() {
+        let mut bots = BotRegistry::default();
+        bots.register("straight", || Box::new(Straight));
+        let options = parse("--bot straight --width 10 --height 10").unwrap();
+        let level = Level::open(&options.board);
+        let record = play(&options, &level, &GameMode::default(), &bots, GameSeed(0));
+        //Going straight up from the middle of the board runs into the wall
+        assert_eq!(record.death, GameState::HitWall);
+    }
+}
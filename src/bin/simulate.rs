@@ -0,0 +1,5 @@
+use super_snake::game::BotRegistry;
+
+fn main() {
+    super_snake::simulate(BotRegistry::default())
+}
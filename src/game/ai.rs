@@ -0,0 +1,339 @@
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+};
+
+use crate::game::{BoardConfig, BoardView, Direction, SnakeController, WallMode, ALL_DIRECTIONS};
+
+// How hard a computer snake tries.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Difficulty {
+    //Heads for the closest pellet and only dodges what is right in front of it
+    Easy,
+    //Finds a path around everything to the closest pellet it can reach
+    #[default]
+    Normal,
+    //Paths to food too, but won't take a move that leaves it less room than its length
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn next(&self) -> Difficulty {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+}
+
+// Steers a computer snake, turning once per tick from the state the MoveSystem will move.
+pub struct AiController {
+    difficulty: Difficulty,
+}
+
+impl AiController {
+    pub fn new(difficulty: Difficulty) -> Self {
+        AiController { difficulty }
+    }
+}
+
+impl SnakeController for AiController {
+    fn steer(&mut self, view: &BoardView) -> Option<Direction> {
+        computer_turn(view, || choose_direction(self.difficulty, view))
+    }
+}
+
+// Waits for the snake's last turn to be taken before thinking about the next one, and only
+// turns when the choice isn't the way it's already going.
+pub(super) fn computer_turn<F: FnOnce() -> Option<Direction>>(
+    view: &BoardView,
+    choose: F,
+) -> Option<Direction> {
+    let snake = view.me();
+    if !snake.is_alive() || !snake.pending_turns.is_empty() {
+        return None;
+    }
+    choose().filter(|&direction| direction != snake.direction)
+}
+
+// The way a computer snake wants to go next, or None if every way is blocked.
+pub fn choose_direction(difficulty: Difficulty, view: &BoardView) -> Option<Direction> {
+    let board = view.board;
+    let snake = view.me();
+    let head = *snake.snake.front()?;
+    let blocked = blocked_cells(view);
+    let food = view.pellets();
+
+    let moves = safe_moves(board, &blocked, head, snake.direction);
+    let greedy = || {
+        moves
+            .iter()
+            .min_by_key(|&&(_, point)| nearest(board, point, &food))
+            .map(|&(direction, _)| direction)
+    };
+
+    match difficulty {
+        Difficulty::Easy => greedy(),
+        Difficulty::Normal => {
+            path_to_food(board, &blocked, head, snake.direction, &food).or_else(greedy)
+        }
+        Difficulty::Hard => {
+            //Only follow the path if there is still room to fit the whole snake afterwards
+            let length = snake.snake.len();
+            let room = |point: Point2<u32>| {
+                let mut after = blocked.clone();
+                after.insert(point);
+                reachable(board, &after, point, length)
+            };
+            let path = path_to_food(board, &blocked, head, snake.direction, &food)
+                .and_then(|direction| moves.iter().find(|&&(d, _)| d == direction))
+                .filter(|&&(_, point)| room(point) >= length)
+                .map(|&(direction, _)| direction);
+
+            //Otherwise go wherever has the most room, closer to food breaking ties
+            path.or_else(|| {
+                moves
+                    .iter()
+                    .max_by_key(|&&(_, point)| (room(point), Reverse(nearest(board, point, &food))))
+                    .map(|&(direction, _)| direction)
+            })
+        }
+    }
+}
+
+// Every wall and snake cell, living or dead.
+pub(super) fn blocked_cells(view: &BoardView) -> HashSet<Point2<u32>> {
+    let mut blocked: HashSet<Point2<u32>> = view.walls.clone();
+    for snake in view.snakes {
+        blocked.extend(snake.snake.iter().cloned());
+    }
+    blocked
+}
+
+// Steps between two cells, going across the edge when the board wraps.
+pub(super) fn distance(board: &BoardConfig, a: Point2<u32>, b: Point2<u32>) -> u32 {
+    let across = |a: u32, b: u32, size: u32| {
+        let gap = a.max(b) - a.min(b);
+        match board.wall_mode {
+            WallMode::Solid => gap,
+            WallMode::Wrap => gap.min(size - gap),
+        }
+    };
+    across(a.x, b.x, board.width) + across(a.y, b.y, board.height)
+}
+
+pub(super) fn nearest(board: &BoardConfig, point: Point2<u32>, food: &[Point2<u32>]) -> u32 {
+    food.iter()
+        .map(|&pellet| distance(board, point, pellet))
+        .min()
+        .unwrap_or(0)
+}
+
+// Every way the snake can move next without dying straight away.
+pub(super) fn safe_moves(
+    board: &BoardConfig,
+    blocked: &HashSet<Point2<u32>>,
+    head: Point2<u32>,
+    heading: Direction,
+) -> Vec<(Direction, Point2<u32>)> {
+    ALL_DIRECTIONS
+        .iter()
+        .filter(|&&direction| direction != heading.opposite())
+        .filter_map(|&direction| {
+            board
+                .neighbour(head, direction)
+                .filter(|point| !blocked.contains(point))
+                .map(|point| (direction, point))
+        })
+        .collect()
+}
+
+// A* to whichever pellet is closest by path, returning the first step to take.
+fn path_to_food(
+    board: &BoardConfig,
+    blocked: &HashSet<Point2<u32>>,
+    head: Point2<u32>,
+    heading: Direction,
+    food: &[Point2<u32>],
+) -> Option<Direction> {
+    if food.is_empty() {
+        return None;
+    }
+
+    let mut open = BinaryHeap::new();
+    let mut cost: HashMap<Point2<u32>, u32> = HashMap::new();
+    let mut first_step: HashMap<Point2<u32>, Direction> = HashMap::new();
+    for (direction, point) in safe_moves(board, blocked, head, heading) {
+        cost.insert(point, 1);
+        first_step.insert(point, direction);
+        open.push(Reverse((
+            1 + nearest(board, point, food),
+            1,
+            (point.x, point.y),
+        )));
+    }
+
+    while let Some(Reverse((_, steps, (x, y)))) = open.pop() {
+        let point = Point2::new(x, y);
+        if food.contains(&point) {
+            return first_step.get(&point).cloned();
+        }
+        if cost[&point] < steps {
+            continue;
+        }
+
+        for &direction in ALL_DIRECTIONS.iter() {
+            let next = match board.neighbour(point, direction) {
+                Some(next) if !blocked.contains(&next) && next != head => next,
+                _ => continue,
+            };
+            if steps + 1 < *cost.get(&next).unwrap_or(&u32::MAX) {
+                cost.insert(next, steps + 1);
+                let step = first_step[&point];
+                first_step.insert(next, step);
+                open.push(Reverse((
+                    steps + 1 + nearest(board, next, food),
+                    steps + 1,
+                    (next.x, next.y),
+                )));
+            }
+        }
+    }
+    None
+}
+
+// Flood fills the open cells from a point, stopping early once it has counted enough.
+fn reachable(
+    board: &BoardConfig,
+    blocked: &HashSet<Point2<u32>>,
+    start: Point2<u32>,
+    enough: usize,
+) -> usize {
+    let mut seen: HashSet<Point2<u32>> = HashSet::new();
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+    while let Some(point) = queue.pop_front() {
+        for &direction in ALL_DIRECTIONS.iter() {
+            if let Some(next) = board.neighbour(point, direction) {
+                if !blocked.contains(&next) && seen.insert(next) {
+                    if seen.len() >= enough {
+                        return seen.len();
+                    }
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    seen.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Food, Snake};
+
+    // What each difficulty picks for a snake heading right with its head at (4, 5) on a
+    // 10x10 board, with the given walls and one pellet.
+    fn choices(walls: &[(u32, u32)], pellet: (u32, u32)) -> [Option<Direction>; 3] {
+        let board = BoardConfig {
+            width: 10,
+            height: 10,
+            wall_mode: WallMode::Solid,
+        };
+        let snake = Snake::new(&board, Point2::new(4, 5), Direction::Right);
+        let snakes = [&snake];
+        let walls: HashSet<_> = walls.iter().map(|&(x, y)| Point2::new(x, y)).collect();
+        let food = Food::new(Some(Point2::new(pellet.0, pellet.1)));
+        let view = BoardView {
+            player: 0,
+            snakes: &snakes,
+            board: &board,
+            walls: &walls,
+            food: &food,
+            tick: 0,
+        };
+        [
+            choose_direction(Difficulty::Easy, &view),
+            choose_direction(Difficulty::Normal, &view),
+            choose_direction(Difficulty::Hard, &view),
+        ]
+    }
+
+    #[test]
+    fn open_fields_are_crossed_straight_to_the_food() {
+        let right = Some(Direction::Right);
+        assert_eq!(choices(&[], (8, 5)), [right, right, right]);
+    }
+
+    #[test]
+    fn paths_go_round_walls_that_greed_runs_into() {
+        //A wall down x = 6 with its gap at the top, and (5, 4) blocked so going right first
+        //means doubling back down and round
+        let mut walls: Vec<(u32, u32)> = (1..10).map(|y| (6, y)).collect();
+        walls.push((5, 4));
+        let [easy, normal, hard] = choices(&walls, (7, 5));
+        assert_eq!(easy, Some(Direction::Right));
+        assert_eq!(normal, Some(Direction::Up));
+        assert_eq!(hard, Some(Direction::Up));
+    }
+
+    #[test]
+    fn hard_snakes_leave_food_in_pockets_too_small_for_them() {
+        //The pellet sits in a dead end only one cell deep
+        let walls = [(6, 5), (5, 4), (5, 6)];
+        let [easy, normal, hard] = choices(&walls, (5, 5));
+        assert_eq!(easy, Some(Direction::Right));
+        assert_eq!(normal, Some(Direction::Right));
+        assert!(
+            hard == Some(Direction::Up) || hard == Some(Direction::Down),
+            "went {:?}",
+            hard
+        );
+    }
+
+    #[test]
+    fn hard_snakes_head_for_the_most_room() {
+        //Up leads into a three cell pocket with the pellet at the end, down into the rest of
+        //the board
+        let mut walls: Vec<(u32, u32)> = (2..5).flat_map(|y| vec![(3, y), (5, y)]).collect();
+        walls.extend(&[(5, 5), (4, 1)]);
+        let [_, normal, hard] = choices(&walls, (4, 2));
+        assert_eq!(normal, Some(Direction::Up));
+        assert_eq!(hard, Some(Direction::Down));
+    }
+
+    #[test]
+    fn boxed_in_snakes_have_nowhere_to_go() {
+        let walls = [(5, 5), (4, 4), (4, 6)];
+        assert_eq!(choices(&walls, (8, 5)), [None, None, None]);
+    }
+
+    #[test]
+    fn flood_fills_count_open_cells_and_stop_when_there_are_enough() {
+        let board = BoardConfig {
+            width: 3,
+            height: 3,
+            wall_mode: WallMode::Solid,
+        };
+        //Walls down the middle column leave the left column cut off
+        let blocked: HashSet<_> = (0..3).map(|y| Point2::new(1, y)).collect();
+        assert_eq!(reachable(&board, &blocked, Point2::new(0, 0), 100), 3);
+        assert_eq!(
+            reachable(&board, &HashSet::new(), Point2::new(0, 0), 100),
+            9
+        );
+        assert_eq!(reachable(&board, &HashSet::new(), Point2::new(0, 0), 4), 4);
+    }
+}
@@ -11,10 +11,11 @@ pub fn play_eat_sound(
     audio_handles: &AudioHandles,
     storage: &AssetStorage<Source>,
     output: Option<&Output>,
+    volume: f32,
 ) {
     if let Some(ref output) = output.as_ref() {
         if let Some(sound) = storage.get(&audio_handles.eating_noise) {
-            output.play_once(sound, 1.0);
+            output.play_once(sound, volume);
         }
     }
 }
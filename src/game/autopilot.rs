@@ -0,0 +1,193 @@
+use nalgebra::Point2;
+
+use crate::game::{
+    ai::{blocked_cells, choose_direction, computer_turn, safe_moves},
+    defines::*,
+    BoardConfig, BoardView, Difficulty, Direction, SnakeController,
+};
+
+// A route through every cell of the board, each one next to the one before, that comes back
+// round to where it started. A snake that follows it only ever runs into cells its tail has
+// already left, so it can fill the whole board.
+pub struct Tour {
+    cells: Vec<Point2<u32>>,
+    //Where each cell comes on the tour, indexed by y * width + x
+    positions: Vec<usize>,
+    width: u32,
+    height: u32,
+}
+
+impl Tour {
+    // There's no tour of a board with an odd number of cells, and this one doesn't go
+    // around walls.
+    pub fn new(board: &BoardConfig) -> Option<Tour> {
+        let (width, height) = (board.width, board.height);
+        if width < 2 || height < 2 || (width % 2 == 1 && height % 2 == 1) {
+            return None;
+        }
+
+        //Run along the first row, zigzag back through the others leaving the first column
+        //free, then come home up that column. The zigzag needs an even number of rows to
+        //finish next to it, so go by columns if there's an odd number.
+        let by_columns = height % 2 == 1;
+        let (across, down) = if by_columns {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let mut route: Vec<(u32, u32)> = (0..across).map(|a| (a, 0)).collect();
+        for d in 1..down {
+            if d % 2 == 1 {
+                route.extend((1..across).rev().map(|a| (a, d)));
+            } else {
+                route.extend((1..across).map(|a| (a, d)));
+            }
+        }
+        route.extend((1..down).rev().map(|d| (0, d)));
+
+        let cells: Vec<Point2<u32>> = route
+            .into_iter()
+            .map(|(a, d)| {
+                if by_columns {
+                    Point2::new(d, a)
+                } else {
+                    Point2::new(a, d)
+                }
+            })
+            .collect();
+        let mut positions = vec![0; cells.len()];
+        for (i, cell) in cells.iter().enumerate() {
+            positions[(cell.y * width + cell.x) as usize] = i;
+        }
+
+        Some(Tour {
+            cells,
+            positions,
+            width,
+            height,
+        })
+    }
+
+    pub fn covers(&self, board: &BoardConfig) -> bool {
+        self.width == board.width && self.height == board.height
+    }
+
+    pub fn position(&self, cell: Point2<u32>) -> usize {
+        self.positions[(cell.y * self.width + cell.x) as usize]
+    }
+
+    // How many steps along the tour it is from one cell to another.
+    pub fn steps(&self, from: Point2<u32>, to: Point2<u32>) -> usize {
+        let len = self.cells.len();
+        (self.position(to) + len - self.position(from)) % len
+    }
+
+    pub fn ahead(&self, from: Point2<u32>, steps: usize) -> Point2<u32> {
+        self.cells[(self.position(from) + steps) % self.cells.len()]
+    }
+}
+
+// Plays in place of a player by following the tour.
+#[derive(Default)]
+pub struct AutopilotController {
+    //Kept between ticks, since it only changes with the board
+    tour: Option<Tour>,
+}
+
+impl SnakeController for AutopilotController {
+    fn steer(&mut self, view: &BoardView) -> Option<Direction> {
+        self.tour = match self.tour.take() {
+            Some(tour) if tour.covers(view.board) => Some(tour),
+            _ => Tour::new(view.board),
+        };
+        let tour = self.tour.as_ref();
+        computer_turn(view, || autopilot_direction(tour, view))
+    }
+}
+
+// The way the autopilot goes next. It follows the tour, cutting across it towards food
+// while the snake is short enough to leave plenty of room behind the cut. Boards the tour
+// can't cover fall back to the hardest computer player.
+fn autopilot_direction(tour: Option<&Tour>, view: &BoardView) -> Option<Direction> {
+    let fallback = || choose_direction(Difficulty::Hard, view);
+    let tour = match tour {
+        Some(tour) if tour.covers(view.board) && view.walls.is_empty() => tour,
+        _ => return fallback(),
+    };
+
+    let snake = view.me();
+    let head = *snake.snake.front()?;
+    let blocked = blocked_cells(view);
+
+    //How far the tour runs before it hits anything
+    let cells = view.board.cell_count();
+    let clear = (1..cells)
+        .find(|&steps| blocked.contains(&tour.ahead(head, steps)))
+        .unwrap_or(cells);
+    if clear <= 1 {
+        return fallback();
+    }
+
+    let food = view
+        .pellets()
+        .into_iter()
+        .map(|pellet| tour.steps(head, pellet))
+        .min()
+        .unwrap_or(cells);
+    let mut best = 1;
+    if snake.snake.len() < cells / 2 {
+        for (_, point) in safe_moves(view.board, &blocked, head, snake.direction) {
+            //Never skip past food, and keep the whole snake's length free beyond the cut
+            let steps = tour.steps(head, point);
+            if steps > best && steps <= food && steps + snake.snake.len() + AUTOPILOT_SLACK < clear
+            {
+                best = steps;
+            }
+        }
+    }
+
+    let target = tour.ahead(head, best);
+    safe_moves(view.board, &blocked, head, snake.direction)
+        .into_iter()
+        .find(|&(_, point)| point == target)
+        .map(|(direction, _)| direction)
+        .or_else(fallback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{play_headless, GameMode, GameSeed, GameState, Level, Simulation, WallMode};
+
+    #[test]
+    fn tour_visits_every_cell_once() {
+        let board = BoardConfig::default();
+        let tour = Tour::new(&board).unwrap();
+        let mut seen = vec![false; board.cell_count()];
+        for steps in 0..board.cell_count() {
+            let cell = tour.ahead(board.center(), steps);
+            let index = (cell.y * board.width + cell.x) as usize;
+            assert!(!seen[index], "({}, {}) came up twice", cell.x, cell.y);
+            seen[index] = true;
+        }
+        assert_eq!(
+            tour.ahead(board.center(), board.cell_count()),
+            board.center()
+        );
+    }
+
+    #[test]
+    fn autopilot_fills_the_default_board() {
+        let board = BoardConfig::default();
+        let (mut simulation, mut snakes) = Simulation::from_level(
+            &Level::open(&board),
+            WallMode::Solid,
+            GameMode::default(),
+            GameSeed(0),
+        );
+        let mut controllers: Vec<Box<dyn SnakeController>> =
+            vec![Box::new(AutopilotController::default())];
+        play_headless(&mut simulation, &mut snakes, &mut controllers, 1_000_000);
+        assert_eq!(snakes[0].state, GameState::BoardFull);
+    }
+}
@@ -0,0 +1,246 @@
+use amethyst::{
+    input::{Bindings, Button, StringBindings},
+    utils::application_root_dir,
+};
+
+use std::{fmt, fs, io, path::PathBuf};
+
+use crate::game::user_config_dir;
+
+// Every action the game listens for, with the name shown on the bindings screen.
+pub const ACTIONS: [(&str, &str); 9] = [
+    ("move_up", "Move Up"),
+    ("move_down", "Move Down"),
+    ("move_left", "Move Left"),
+    ("move_right", "Move Right"),
+    ("pause", "Pause"),
+    ("confirm", "Confirm"),
+    ("back", "Back"),
+    ("save", "Save Game"),
+    ("replay", "Watch Replay"),
+];
+
+// The steering actions every player has their own copy of.
+pub const MOVE_ACTIONS: [(&str, &str); 4] = [
+    ("move_up", "Move Up"),
+    ("move_down", "Move Down"),
+    ("move_left", "Move Left"),
+    ("move_right", "Move Right"),
+];
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(io::Error),
+    Write(ron::ser::Error),
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingsError::Io(e) => write!(f, "could not access bindings file: {}", e),
+            BindingsError::Write(e) => write!(f, "could not write bindings file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BindingsError {}
+
+impl From<io::Error> for BindingsError {
+    fn from(e: io::Error) -> Self {
+        BindingsError::Io(e)
+    }
+}
+
+impl From<ron::ser::Error> for BindingsError {
+    fn from(e: ron::ser::Error) -> Self {
+        BindingsError::Write(e)
+    }
+}
+
+// The name of an action for one player. The first player's are the plain names, the rest
+// are prefixed like "p2_move_up".
+pub fn player_action(player: usize, action: &str) -> String {
+    if player == 0 {
+        action.to_string()
+    } else {
+        format!("p{}_{}", player + 1, action)
+    }
+}
+
+// The actions listed on the bindings screen for a player. The first player also owns the
+// menu actions.
+pub fn player_actions(player: usize) -> Vec<(String, &'static str)> {
+    if player == 0 {
+        ACTIONS
+            .iter()
+            .map(|&(action, label)| (action.to_string(), label))
+            .collect()
+    } else {
+        MOVE_ACTIONS
+            .iter()
+            .map(|&(action, label)| (player_action(player, action), label))
+            .collect()
+    }
+}
+
+// The player's own bindings if they have rebound anything, otherwise the shipped ones.
+// Actions and axes missing from the player's file, like the ones added since it was saved,
+// keep their shipped bindings.
+pub fn load_bindings() -> Bindings<StringBindings> {
+    let path = user_bindings_path();
+    match fs::File::open(&path) {
+        Ok(file) => match ron::de::from_reader(file) {
+            Ok(bindings) => with_defaults(bindings, &default_bindings()),
+            Err(e) => {
+                log::warn!("Ignoring bindings in {}: {}", path.display(), e);
+                default_bindings()
+            }
+        },
+        Err(_) => default_bindings(),
+    }
+}
+
+fn with_defaults(
+    mut bindings: Bindings<StringBindings>,
+    defaults: &Bindings<StringBindings>,
+) -> Bindings<StringBindings> {
+    for action in defaults.actions() {
+        if bindings.action_bindings(action.as_str()).next().is_some() {
+            continue;
+        }
+        for combo in defaults.action_bindings(action.as_str()) {
+            //A shipped button the player has since given to another action stays with it
+            bindings
+                .insert_action_binding(action.clone(), combo.to_vec())
+                .ok();
+        }
+    }
+    for axis in defaults.axes() {
+        if bindings.axis(axis.as_str()).is_some() {
+            continue;
+        }
+        if let Some(value) = defaults.axis(axis.as_str()) {
+            bindings.insert_axis(axis.clone(), value.clone()).ok();
+        }
+    }
+    bindings
+}
+
+pub fn default_bindings() -> Bindings<StringBindings> {
+    let path = application_root_dir()
+        .unwrap_or_default()
+        .join("config")
+        .join("bindings.ron");
+    fs::File::open(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| ron::de::from_reader(file).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            log::warn!("Could not load bindings from {}: {}", path.display(), e);
+            Bindings::default()
+        })
+}
+
+pub fn save_bindings(bindings: &Bindings<StringBindings>) -> Result<(), BindingsError> {
+    let path = user_bindings_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text = ron::ser::to_string_pretty(bindings, ron::ser::PrettyConfig::default())?;
+    fs::write(path, text)?;
+    Ok(())
+}
+
+// Binds a button to an action in place of one of its combos, or alongside them when it's
+// not replacing any. A button can only trigger one action, so it is taken off whatever had
+// it before.
+pub fn rebind(
+    bindings: &mut Bindings<StringBindings>,
+    action: &str,
+    replacing: Option<&[Button]>,
+    button: Button,
+) {
+    let owners: Vec<String> = bindings
+        .actions()
+        .filter(|other| {
+            bindings
+                .action_bindings(other.as_str())
+                .any(|combo| combo == [button])
+        })
+        .cloned()
+        .collect();
+    for owner in owners {
+        bindings.remove_action_binding(owner.as_str(), &[button]);
+    }
+
+    if let Some(combo) = replacing {
+        bindings.remove_action_binding(action, combo);
+    }
+
+    if let Err(e) = bindings.insert_action_binding(action.to_string(), vec![button]) {
+        log::warn!("Could not bind {}: {}", action, e);
+    }
+}
+
+// The combos bound to an action on the same device as a button, so the bindings screen can
+// pick which one a new key replaces.
+pub fn device_bindings(
+    bindings: &Bindings<StringBindings>,
+    action: &str,
+    button: &Button,
+) -> Vec<Vec<Button>> {
+    bindings
+        .action_bindings(action)
+        .filter(|combo| combo.iter().all(|other| same_device(other, button)))
+        .map(|combo| combo.to_vec())
+        .collect()
+}
+
+// Every button bound to an action, for the bindings screen.
+pub fn describe_action(bindings: &Bindings<StringBindings>, action: &str) -> String {
+    let buttons: Vec<String> = bindings
+        .action_bindings(action)
+        .map(describe_combo)
+        .collect();
+    if buttons.is_empty() {
+        "Unbound".to_string()
+    } else {
+        buttons.join(", ")
+    }
+}
+
+pub fn describe_combo(combo: &[Button]) -> String {
+    combo.iter().map(button_name).collect::<Vec<_>>().join("+")
+}
+
+// The first button bound to an action, for prompts like "Press Space to Start".
+pub fn action_key_name(bindings: &Bindings<StringBindings>, action: &str) -> String {
+    bindings
+        .action_bindings(action)
+        .next()
+        .and_then(|combo| combo.first().map(button_name))
+        .unwrap_or_else(|| "?".to_string())
+}
+
+fn same_device(a: &Button, b: &Button) -> bool {
+    match (a, b) {
+        (Button::Controller(a, _), Button::Controller(b, _)) => a == b,
+        (Button::Mouse(_), Button::Mouse(_)) => true,
+        (Button::Key(_), Button::Key(_))
+        | (Button::Key(_), Button::ScanCode(_))
+        | (Button::ScanCode(_), Button::Key(_))
+        | (Button::ScanCode(_), Button::ScanCode(_)) => true,
+        _ => false,
+    }
+}
+
+fn button_name(button: &Button) -> String {
+    match button {
+        Button::Key(key) => format!("{:?}", key),
+        Button::Controller(_, button) => format!("Pad {:?}", button),
+        other => format!("{:?}", other),
+    }
+}
+
+fn user_bindings_path() -> PathBuf {
+    user_config_dir().join("bindings.ron")
+}
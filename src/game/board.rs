@@ -0,0 +1,156 @@
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{defines::*, Direction};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum WallMode {
+    Solid,
+    Wrap,
+}
+
+impl WallMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WallMode::Solid => "Solid",
+            WallMode::Wrap => "Wrap",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct BoardConfig {
+    pub width: u32,
+    pub height: u32,
+    pub wall_mode: WallMode,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        BoardConfig {
+            width: DEFAULT_BOARD_WIDTH,
+            height: DEFAULT_BOARD_HEIGHT,
+            wall_mode: WallMode::Solid,
+        }
+    }
+}
+
+impl BoardConfig {
+    pub fn cell_count(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    // The cell one step away, or None if that leaves a board with solid walls.
+    pub fn neighbour(&self, cell: Point2<u32>, direction: Direction) -> Option<Point2<u32>> {
+        let (x, y) = (i64::from(cell.x), i64::from(cell.y));
+        let (x, y) = match direction {
+            Direction::Up => (x, y - 1),
+            Direction::Down => (x, y + 1),
+            Direction::Left => (x - 1, y),
+            Direction::Right => (x + 1, y),
+        };
+        let (width, height) = (i64::from(self.width), i64::from(self.height));
+
+        match self.wall_mode {
+            WallMode::Solid => {
+                if x < 0 || y < 0 || x >= width || y >= height {
+                    None
+                } else {
+                    Some(Point2::new(x as u32, y as u32))
+                }
+            }
+            WallMode::Wrap => Some(Point2::new(
+                x.rem_euclid(width) as u32,
+                y.rem_euclid(height) as u32,
+            )),
+        }
+    }
+
+    pub fn center(&self) -> Point2<u32> {
+        Point2::new(self.width / 2, self.height / 2)
+    }
+
+    // Size of the square camera view that fits the whole board.
+    pub fn view_size(&self) -> f32 {
+        (TILE_SIZE * self.width.max(self.height)) as f32
+    }
+
+    // The cell under a world position, the inverse of cell_center.
+    pub fn cell_at(&self, x: f32, y: f32) -> Option<Point2<u32>> {
+        let half_width = (TILE_SIZE * self.width) as f32 / 2.0;
+        let half_height = (TILE_SIZE * self.height) as f32 / 2.0;
+        let cell_x = ((x + half_width) / TILE_SIZE as f32).floor();
+        let cell_y = ((half_height - y) / TILE_SIZE as f32).floor();
+
+        if cell_x < 0.0 || cell_y < 0.0 {
+            return None;
+        }
+        let cell = Point2::new(cell_x as u32, cell_y as u32);
+        if cell.x < self.width && cell.y < self.height {
+            Some(cell)
+        } else {
+            None
+        }
+    }
+
+    // World position of the middle of a cell, with the camera centred on the board.
+    pub fn cell_center(&self, cell: Point2<u32>) -> (f32, f32) {
+        let half_width = (TILE_SIZE * self.width) as f32 / 2.0;
+        let half_height = (TILE_SIZE * self.height) as f32 / 2.0;
+        (
+            (cell.x as f32 + 0.5) * TILE_SIZE as f32 - half_width,
+            half_height - (cell.y as f32 + 0.5) * TILE_SIZE as f32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(wall_mode: WallMode) -> BoardConfig {
+        BoardConfig {
+            width: 10,
+            height: 6,
+            wall_mode,
+        }
+    }
+
+    #[test]
+    fn wrap_comes_round_every_edge() {
+        let board = board(WallMode::Wrap);
+        let corner = Point2::new(0, 0);
+        let far = Point2::new(9, 5);
+        assert_eq!(
+            board.neighbour(corner, Direction::Up),
+            Some(Point2::new(0, 5))
+        );
+        assert_eq!(
+            board.neighbour(corner, Direction::Left),
+            Some(Point2::new(9, 0))
+        );
+        assert_eq!(
+            board.neighbour(far, Direction::Down),
+            Some(Point2::new(9, 0))
+        );
+        assert_eq!(
+            board.neighbour(far, Direction::Right),
+            Some(Point2::new(0, 5))
+        );
+    }
+
+    #[test]
+    fn solid_walls_stop_at_every_edge() {
+        let board = board(WallMode::Solid);
+        let corner = Point2::new(0, 0);
+        let far = Point2::new(9, 5);
+        assert_eq!(board.neighbour(corner, Direction::Up), None);
+        assert_eq!(board.neighbour(corner, Direction::Left), None);
+        assert_eq!(board.neighbour(far, Direction::Down), None);
+        assert_eq!(board.neighbour(far, Direction::Right), None);
+        assert_eq!(
+            board.neighbour(corner, Direction::Right),
+            Some(Point2::new(1, 0))
+        );
+    }
+}
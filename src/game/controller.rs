@@ -0,0 +1,171 @@
+#[cfg(feature = "engine")]
+use amethyst::ecs::prelude::*;
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::game::{
+    AiController, AutopilotController, BoardConfig, Difficulty, Direction, Food,
+    KeyboardController, Simulation, Snake,
+};
+
+// What steers a snake.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub enum Controller {
+    //A player's own bindings, by player number
+    Keyboard(usize),
+    //A computer player, thinking as hard as its difficulty allows
+    Ai(Difficulty),
+    //Following the tour around the board in place of a player, who gets it back when the
+    //autopilot is switched off
+    Autopilot(usize),
+    //A bot from another crate, by the name it was registered under
+    Bot(String),
+    //The turns recorded in the replay being watched
+    Replay,
+}
+
+#[cfg(feature = "engine")]
+impl Component for Controller {
+    type Storage = DenseVecStorage<Self>;
+}
+
+impl Controller {
+    // The pilot that does the steering for this kind of controller, given every snake's
+    // controller. Replays steer themselves so have none, and neither do bots that aren't
+    // registered any more.
+    pub fn pilot(&self, controllers: &[Controller], bots: &BotRegistry) -> Option<Pilot> {
+        let bot: Box<dyn SnakeController> = match self {
+            Controller::Keyboard(player) => {
+                //Everyone's controls steer a lone player's snake, so any set of keys works
+                //in a solo game
+                let keyboards = controllers
+                    .iter()
+                    .filter(|controller| matches!(controller, Controller::Keyboard(_)))
+                    .count();
+                return Some(Pilot::Keyboard(if keyboards == 1 {
+                    KeyboardController::everyone()
+                } else {
+                    KeyboardController::new(*player)
+                }));
+            }
+            Controller::Ai(difficulty) => Box::new(AiController::new(*difficulty)),
+            Controller::Autopilot(_) => Box::new(AutopilotController::default()),
+            Controller::Bot(name) => match bots.create(name) {
+                Some(bot) => bot,
+                None => {
+                    log::warn!("No bot called {} is registered", name);
+                    return None;
+                }
+            },
+            Controller::Replay => return None,
+        };
+        Some(Pilot::Bot(bot))
+    }
+}
+
+// Anything that can steer a snake, whether that's a person, the computer or a bot from
+// another crate. It's asked which way to go before every tick, and any direction it gives is
+// queued as a turn just like a key press.
+pub trait SnakeController: Send + Sync {
+    fn steer(&mut self, view: &BoardView) -> Option<Direction>;
+}
+
+// Everything a controller can see of the game, without being able to change any of it.
+pub struct BoardView<'a> {
+    //The snake being steered, as an index into snakes
+    pub player: usize,
+    pub snakes: &'a [&'a Snake],
+    pub board: &'a BoardConfig,
+    pub walls: &'a HashSet<Point2<u32>>,
+    pub food: &'a Food,
+    pub tick: u32,
+}
+
+impl<'a> BoardView<'a> {
+    pub fn new(simulation: &'a Simulation, snakes: &'a [&'a Snake], player: usize) -> Self {
+        BoardView {
+            player,
+            snakes,
+            board: &simulation.board,
+            walls: &simulation.walls,
+            food: &simulation.food,
+            tick: simulation.ticks,
+        }
+    }
+
+    pub fn me(&self) -> &'a Snake {
+        self.snakes[self.player]
+    }
+
+    // Every other snake with its index, living or dead.
+    pub fn others(&self) -> impl Iterator<Item = (usize, &'a Snake)> + '_ {
+        self.snakes
+            .iter()
+            .enumerate()
+            .filter(move |&(index, _)| index != self.player)
+            .map(|(index, snake)| (index, *snake))
+    }
+
+    // Where every pellet is, the bonus one included.
+    pub fn pellets(&self) -> Vec<Point2<u32>> {
+        let mut pellets: Vec<Point2<u32>> = self.food.pellets.keys().cloned().collect();
+        pellets.extend(self.food.bonus.as_ref().map(|bonus| bonus.point));
+        pellets
+    }
+}
+
+// The controller steering a snake's entity. Keyboard controllers are kept apart so the
+// DirectionChangeSystem can hand them the players' key presses.
+pub enum Pilot {
+    Keyboard(KeyboardController),
+    Bot(Box<dyn SnakeController>),
+}
+
+impl Pilot {
+    pub fn controller(&mut self) -> &mut dyn SnakeController {
+        match self {
+            Pilot::Keyboard(keyboard) => keyboard,
+            Pilot::Bot(bot) => bot.as_mut(),
+        }
+    }
+
+    pub fn into_controller(self) -> Box<dyn SnakeController> {
+        match self {
+            Pilot::Keyboard(keyboard) => Box::new(keyboard),
+            Pilot::Bot(bot) => bot,
+        }
+    }
+}
+
+#[cfg(feature = "engine")]
+impl Component for Pilot {
+    type Storage = DenseVecStorage<Self>;
+}
+
+type BotFactory = Box<dyn Fn() -> Box<dyn SnakeController> + Send + Sync>;
+
+// Bots from other crates that can play in the game, each made fresh for every snake it
+// steers. Computer snakes can be set to play as any of them.
+#[derive(Default)]
+pub struct BotRegistry {
+    bots: BTreeMap<String, BotFactory>,
+}
+
+impl BotRegistry {
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn() -> Box<dyn SnakeController> + Send + Sync + 'static,
+    {
+        self.bots.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn create(&self, name: &str) -> Option<Box<dyn SnakeController>> {
+        self.bots.get(name).map(|factory| factory())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.bots.keys().cloned().collect()
+    }
+}
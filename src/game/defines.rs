@@ -1,3 +1,28 @@
 pub const TILE_SIZE: u32 = 32;
-pub const GRID_SIZE: u32 = 40;
-pub const MOVEMENT_PERIOD: f32 = 0.05;
+pub const DEFAULT_BOARD_WIDTH: u32 = 40;
+pub const DEFAULT_BOARD_HEIGHT: u32 = 40;
+pub const MAX_CATCH_UP_TICKS: u32 = 5;
+pub const START_LENGTH: usize = 4;
+pub const MAX_PENDING_TURNS: usize = 3;
+pub const MIN_SNAKE_LENGTH: usize = 2;
+pub const GOLDEN_POINTS: u32 = 5;
+pub const SHRINK_SEGMENTS: usize = 3;
+pub const SPEED_EFFECT_TICKS: u32 = 100;
+pub const FAST_PERIOD_SCALE: f32 = 0.6;
+pub const SLOW_PERIOD_SCALE: f32 = 1.6;
+pub const BONUS_SPAWN_ODDS: u32 = 200;
+pub const BONUS_LIFETIME_TICKS: u32 = 150;
+pub const BONUS_BLINK_TICKS: u32 = 40;
+pub const BONUS_BLINKS_PER_SECOND: f64 = 4.0;
+pub const BONUS_MAX_POINTS: u32 = 20;
+pub const MIN_BOARD_SIZE: u32 = 10;
+pub const MAX_BOARD_SIZE: u32 = 80;
+pub const STICK_DEADZONE: f32 = 0.35;
+pub const AUTOPILOT_SLACK: usize = 4;
+pub const MAX_PLAYERS: usize = 4;
+pub const PLAYER_TINTS: [[f32; 4]; MAX_PLAYERS] = [
+    [1.0, 1.0, 1.0, 1.0],
+    [0.5, 0.7, 1.0, 1.0],
+    [1.0, 0.5, 0.5, 1.0],
+    [1.0, 0.9, 0.4, 1.0],
+];
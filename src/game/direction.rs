@@ -1,12 +1,9 @@
-use crate::game::Snake;
-use crate::states::GameState;
-use amethyst::{
-    core::math::Point2,
-    ecs::prelude::*,
-    input::{InputHandler, StringBindings, VirtualKeyCode},
-};
-
-#[derive(PartialEq, Eq, Clone)]
+use crate::game::{defines::*, BoardView, SnakeController};
+use serde::{Deserialize, Serialize};
+
+use std::collections::VecDeque;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
 pub enum Direction {
     Up,
     Down,
@@ -14,53 +11,181 @@ pub enum Direction {
     Right,
 }
 
-pub struct DirectionChangeSystem {}
-
-impl<'s> System<'s> for DirectionChangeSystem {
-    type SystemData = (
-        WriteExpect<'s, Snake>,
-        Read<'s, InputHandler<StringBindings>>,
-        Read<'s, GameState>,
-    );
-
-    fn run(&mut self, (mut snake, input, game_state): Self::SystemData) {
-        if *game_state == GameState::Playing {
-            if input.key_is_down(VirtualKeyCode::Up) {
-                let new_point = Point2::new(
-                    snake.snake.get(0).unwrap().x,
-                    snake.snake.get(0).unwrap().y - 1,
-                );
-                if new_point != *snake.snake.get(1).unwrap() {
-                    snake.direction = Direction::Up;
-                }
-            }
-            if input.key_is_down(VirtualKeyCode::Down) {
-                let new_point = Point2::new(
-                    snake.snake.get(0).unwrap().x,
-                    snake.snake.get(0).unwrap().y + 1,
-                );
-                if new_point != *snake.snake.get(1).unwrap() {
-                    snake.direction = Direction::Down;
-                }
-            }
-            if input.key_is_down(VirtualKeyCode::Left) {
-                let new_point = Point2::new(
-                    snake.snake.get(0).unwrap().x - 1,
-                    snake.snake.get(0).unwrap().y,
-                );
-                if new_point != *snake.snake.get(1).unwrap() {
-                    snake.direction = Direction::Left;
-                }
+// Every way a snake can head, for going through them in turn.
+pub const ALL_DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+impl Direction {
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    // Reads an analog stick with up as positive y. Inside the deadzone there's no direction,
+    // otherwise whichever axis is pushed further wins.
+    pub fn from_stick(x: f32, y: f32) -> Option<Direction> {
+        if x * x + y * y < STICK_DEADZONE * STICK_DEADZONE {
+            None
+        } else if x.abs() > y.abs() {
+            Some(if x > 0.0 {
+                Direction::Right
+            } else {
+                Direction::Left
+            })
+        } else {
+            Some(if y > 0.0 {
+                Direction::Up
+            } else {
+                Direction::Down
+            })
+        }
+    }
+}
+
+// Steers with the key presses of the players it listens to, which the
+// DirectionChangeSystem passes on, taking them one turn at a time.
+pub struct KeyboardController {
+    players: Vec<usize>,
+    presses: VecDeque<Direction>,
+    //Where the snake was last seen heading, counting the turns already handed to it
+    heading: Option<Direction>,
+}
+
+impl KeyboardController {
+    pub fn new(player: usize) -> Self {
+        Self::listening_to(vec![player])
+    }
+
+    pub fn everyone() -> Self {
+        Self::listening_to((0..MAX_PLAYERS).collect())
+    }
+
+    fn listening_to(players: Vec<usize>) -> Self {
+        KeyboardController {
+            players,
+            presses: VecDeque::new(),
+            heading: None,
+        }
+    }
+
+    pub fn listens_to(&self, player: usize) -> bool {
+        self.players.contains(&player)
+    }
+
+    // Holds on to a press until the snake can take it, dropping any beyond what the snake
+    // could queue anyway. Like the snake's own queue, a press that carries on the way the
+    // last one went or doubles back on it isn't a turn, so it doesn't take up a slot.
+    pub fn press(&mut self, direction: Direction) {
+        if let Some(follows) = self.presses.back().cloned().or(self.heading) {
+            if direction == follows || direction == follows.opposite() {
+                return;
             }
-            if input.key_is_down(VirtualKeyCode::Right) {
-                let new_point = Point2::new(
-                    snake.snake.get(0).unwrap().x + 1,
-                    snake.snake.get(0).unwrap().y,
-                );
-                if new_point != *snake.snake.get(1).unwrap() {
-                    snake.direction = Direction::Right;
-                }
+        }
+        if self.presses.len() < MAX_PENDING_TURNS {
+            self.presses.push_back(direction);
+        }
+    }
+}
+
+impl SnakeController for KeyboardController {
+    // Hands over the first press that's still a turn, since the snake may have turned since
+    // the presses were checked.
+    fn steer(&mut self, view: &BoardView) -> Option<Direction> {
+        let snake = view.me();
+        let follows = *snake.pending_turns.back().unwrap_or(&snake.direction);
+        self.heading = Some(follows);
+        while let Some(direction) = self.presses.pop_front() {
+            if direction != follows && direction != follows.opposite() {
+                self.heading = Some(direction);
+                return Some(direction);
             }
         }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{steer_snakes, BoardConfig, GameMode, GameSeed, Level, Simulation, WallMode};
+
+    #[test]
+    fn presses_that_are_not_turns_are_dropped() {
+        let board = BoardConfig::default();
+        let (mut simulation, mut snakes) = Simulation::from_level(
+            &Level::open(&board),
+            WallMode::Solid,
+            GameMode::default(),
+            GameSeed(0),
+        );
+        assert_eq!(snakes[0].direction, Direction::Up);
+
+        let mut keyboard = KeyboardController::new(0);
+        for &direction in [
+            Direction::Up,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ]
+        .iter()
+        {
+            keyboard.press(direction);
+        }
+        let mut headings = Vec::new();
+        for _ in 0..4 {
+            steer_snakes(&mut simulation, &mut snakes, vec![(0, &mut keyboard)]);
+            simulation.tick(&mut snakes);
+            headings.push(snakes[0].direction);
+        }
+        assert_eq!(
+            headings,
+            vec![
+                Direction::Right,
+                Direction::Down,
+                Direction::Down,
+                Direction::Down
+            ]
+        );
+    }
+
+    #[test]
+    fn presses_are_checked_against_the_heading_once_it_is_known() {
+        let board = BoardConfig::default();
+        let (mut simulation, mut snakes) = Simulation::from_level(
+            &Level::open(&board),
+            WallMode::Solid,
+            GameMode::default(),
+            GameSeed(0),
+        );
+        let mut keyboard = KeyboardController::new(0);
+        steer_snakes(&mut simulation, &mut snakes, vec![(0, &mut keyboard)]);
+        //Heading up, so neither of these is a turn and neither takes a slot
+        keyboard.press(Direction::Up);
+        keyboard.press(Direction::Down);
+        for &direction in [Direction::Left, Direction::Up, Direction::Right].iter() {
+            keyboard.press(direction);
+        }
+        assert_eq!(
+            keyboard.presses,
+            vec![Direction::Left, Direction::Up, Direction::Right]
+        );
+    }
+
+    #[test]
+    fn sticks_go_the_way_they_are_pushed_furthest() {
+        assert_eq!(Direction::from_stick(0.0, 0.0), None);
+        assert_eq!(Direction::from_stick(STICK_DEADZONE / 2.0, 0.0), None);
+        assert_eq!(Direction::from_stick(0.9, 0.3), Some(Direction::Right));
+        assert_eq!(Direction::from_stick(-0.9, 0.3), Some(Direction::Left));
+        assert_eq!(Direction::from_stick(0.3, 0.9), Some(Direction::Up));
+        assert_eq!(Direction::from_stick(0.3, -0.9), Some(Direction::Down));
     }
 }
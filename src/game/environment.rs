@@ -0,0 +1,449 @@
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+use crate::game::{
+    ai::{blocked_cells, distance},
+    defines::*,
+    steer_snakes, AiController, BoardConfig, BoardView, Difficulty, Direction, GameMode, GameSeed,
+    GameState, Level, LevelError, Simulation, Snake, SnakeController, StepOutcome, WallMode,
+    WinCondition, ALL_DIRECTIONS,
+};
+
+// Layers in the grid observation, each covering the whole board.
+pub const GRID_CHANNELS: usize = 4;
+// Values in the feature observation.
+pub const FEATURE_COUNT: usize = 11;
+
+// What a learning agent gets for each thing that can happen to its snake.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RewardShaping {
+    //For every point scored
+    pub points: f32,
+    //For every tick played
+    pub step: f32,
+    //For every cell the head gets closer to the nearest pellet, taken away for going further
+    pub closer: f32,
+    pub death: f32,
+    //For filling the board alone, or winning the game against other snakes
+    pub win: f32,
+}
+
+impl Default for RewardShaping {
+    fn default() -> Self {
+        RewardShaping {
+            points: 1.0,
+            step: 0.0,
+            closer: 0.0,
+            death: -1.0,
+            win: 1.0,
+        }
+    }
+}
+
+// The game an agent learns on.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct EnvConfig {
+    pub board: BoardConfig,
+    //Played instead of an open board when there is one
+    pub level: Option<Level>,
+    pub mode: GameMode,
+    //Computer snakes playing against the agent
+    pub opponents: usize,
+    pub difficulty: Difficulty,
+    pub win_condition: WinCondition,
+    //Episodes still going after this many ticks are cut off
+    pub max_ticks: u32,
+    pub rewards: RewardShaping,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            board: BoardConfig::default(),
+            level: None,
+            mode: GameMode::default(),
+            opponents: 0,
+            difficulty: Difficulty::default(),
+            win_condition: WinCondition::default(),
+            max_ticks: 100_000,
+            rewards: RewardShaping::default(),
+        }
+    }
+}
+
+// What the agent sees of the game.
+#[derive(Clone, Debug)]
+pub struct Observation {
+    //GRID_CHANNELS layers of cells: the agent's head, every snake's body, food and walls, each
+    //laid out at y * width + x and set to 1.0 where there's something there
+    pub grid: Vec<f32>,
+    //Whether each of ALL_DIRECTIONS is blocked next to the head, the heading in the same
+    //order, the offset to the nearest pellet as a fraction of the board, and the snake's
+    //length as a fraction of the cells
+    pub features: Vec<f32>,
+}
+
+// The rest of what happened on a step, for keeping track of training.
+#[derive(Serialize, Clone, Debug)]
+pub struct StepInfo {
+    pub score: u32,
+    pub length: usize,
+    pub ticks: u32,
+    //How the agent's snake is doing, so how it died once the episode is done
+    pub state: GameState,
+    //Cut off by max_ticks rather than ended by the rules
+    pub truncated: bool,
+}
+
+#[derive(Debug)]
+pub enum EnvError {
+    //The level, or the open board when there isn't one, can't be played
+    Level(LevelError),
+    //More opponents than there are snakes to go round
+    TooManyOpponents(usize),
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::Level(e) => write!(f, "{}", e),
+            EnvError::TooManyOpponents(count) => write!(
+                f,
+                "{} opponents is more than the {} there's room for",
+                count,
+                MAX_PLAYERS - 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EnvError {}
+
+impl From<LevelError> for EnvError {
+    fn from(e: LevelError) -> Self {
+        EnvError::Level(e)
+    }
+}
+
+// A gym style environment for learning to play, with the agent steering the first snake
+// and any opponents played by the computer.
+pub struct SnakeEnv {
+    config: EnvConfig,
+    simulation: Simulation,
+    snakes: Vec<Snake>,
+    opponents: Vec<Box<dyn SnakeController>>,
+}
+
+impl SnakeEnv {
+    // Checks the game can be played before setting it up, so episodes can always start.
+    pub fn new(config: EnvConfig) -> Result<Self, EnvError> {
+        if config.opponents >= MAX_PLAYERS {
+            return Err(EnvError::TooManyOpponents(config.opponents));
+        }
+        let level = level(&config);
+        level.validate()?;
+        level.spawn_points(1 + config.opponents)?;
+
+        let (simulation, snakes, opponents) = start(&config, GameSeed(0));
+        Ok(SnakeEnv {
+            config,
+            simulation,
+            snakes,
+            opponents,
+        })
+    }
+
+    // Starts a new episode from a seed, so the same seed always plays out the same way.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        let (simulation, snakes, opponents) = start(&self.config, GameSeed(seed));
+        self.simulation = simulation;
+        self.snakes = snakes;
+        self.opponents = opponents;
+        self.observe()
+    }
+
+    // Turns the agent's snake and moves the game on a tick. Going straight on or back the
+    // way the snake came leaves it heading the way it was.
+    pub fn step(&mut self, action: Direction) -> (Observation, f32, bool, StepInfo) {
+        if self.is_done() {
+            return (self.observe(), 0.0, true, self.info());
+        }
+        let rewards = self.config.rewards.clone();
+        let points = self.snakes[0].score.points;
+        let food = self.food_distance();
+
+        self.simulation.turn(&mut self.snakes, 0, action);
+        self.steer_opponents();
+        let outcomes = self.simulation.tick(&mut self.snakes);
+        self.play_out();
+
+        let snake = &self.snakes[0];
+        let mut reward = rewards.step + rewards.points * (snake.score.points - points) as f32;
+        //Nothing to get closer to while the board is bare
+        if let (StepOutcome::Moved, Some(before), Some(after)) =
+            (outcomes[0], food, self.food_distance())
+        {
+            reward += rewards.closer * (before as f32 - after as f32);
+        }
+        match snake.state {
+            GameState::Playing | GameState::BoardFull => {}
+            _ => reward += rewards.death,
+        }
+        if self.is_done() && self.won() {
+            reward += rewards.win;
+        }
+
+        (self.observe(), reward, self.is_done(), self.info())
+    }
+
+    pub fn observe(&self) -> Observation {
+        let board = &self.simulation.board;
+        let width = board.width as usize;
+        let cells = board.cell_count();
+        let snakes: Vec<&Snake> = self.snakes.iter().collect();
+        let view = BoardView::new(&self.simulation, &snakes, 0);
+        let me = view.me();
+        let head = *me.snake.front().unwrap();
+
+        let mut grid = vec![0.0; GRID_CHANNELS * cells];
+        let mut mark = |channel: usize, x: u32, y: u32| {
+            grid[channel * cells + y as usize * width + x as usize] = 1.0;
+        };
+        mark(0, head.x, head.y);
+        for snake in view.snakes.iter() {
+            for point in snake.snake.iter().filter(|&&point| point != head) {
+                mark(1, point.x, point.y);
+            }
+        }
+        for point in view.pellets() {
+            mark(2, point.x, point.y);
+        }
+        for point in view.walls.iter() {
+            mark(3, point.x, point.y);
+        }
+
+        let blocked = blocked_cells(&view);
+        let mut features = Vec::with_capacity(FEATURE_COUNT);
+        for &direction in ALL_DIRECTIONS.iter() {
+            let open = board
+                .neighbour(head, direction)
+                .filter(|point| !blocked.contains(point));
+            features.push(if open.is_some() { 0.0 } else { 1.0 });
+        }
+        for &direction in ALL_DIRECTIONS.iter() {
+            features.push(if me.direction == direction { 1.0 } else { 0.0 });
+        }
+        //Ties go by position, so the same game is always seen the same way
+        let pellet = view
+            .pellets()
+            .into_iter()
+            .min_by_key(|&pellet| (distance(board, head, pellet), pellet.y, pellet.x));
+        //On a wrapping board the pellet may be nearer going out over the edge
+        let offset = |to: u32, from: u32, size: u32| {
+            let offset = to as f32 - from as f32;
+            let size = size as f32;
+            if board.wall_mode == WallMode::Wrap && offset.abs() > size / 2.0 {
+                offset - offset.signum() * size
+            } else {
+                offset
+            }
+        };
+        let (dx, dy) = pellet.map_or((0.0, 0.0), |pellet| {
+            (
+                offset(pellet.x, head.x, board.width) / board.width as f32,
+                offset(pellet.y, head.y, board.height) / board.height as f32,
+            )
+        });
+        features.push(dx);
+        features.push(dy);
+        features.push(me.snake.len() as f32 / cells as f32);
+
+        Observation { grid, features }
+    }
+
+    pub fn info(&self) -> StepInfo {
+        let snake = &self.snakes[0];
+        StepInfo {
+            score: snake.score.points,
+            length: snake.snake.len(),
+            ticks: self.simulation.ticks,
+            state: snake.state,
+            truncated: snake.is_alive()
+                && !self.simulation.is_over(&self.snakes)
+                && self.simulation.ticks >= self.config.max_ticks,
+        }
+    }
+
+    // The episode ends when the agent's snake is out of the game, or the game itself is.
+    pub fn is_done(&self) -> bool {
+        !self.snakes[0].is_alive()
+            || self.simulation.is_over(&self.snakes)
+            || self.simulation.ticks >= self.config.max_ticks
+    }
+
+    // Whether the agent's snake filled the board or is among the winners of a game that's
+    // over.
+    fn won(&self) -> bool {
+        if self.snakes.len() == 1 {
+            return self.snakes[0].state == GameState::BoardFull;
+        }
+        self.simulation.is_over(&self.snakes) && self.simulation.winners(&self.snakes).contains(&0)
+    }
+
+    // Once the agent's snake is out, the opponents play on to settle who won, since on
+    // highest score a snake that died early can still come out on top.
+    fn play_out(&mut self) {
+        if self.snakes[0].is_alive() {
+            return;
+        }
+        while !self.simulation.is_over(&self.snakes)
+            && self.simulation.ticks < self.config.max_ticks
+        {
+            self.steer_opponents();
+            self.simulation.tick(&mut self.snakes);
+        }
+    }
+
+    fn steer_opponents(&mut self) {
+        steer_snakes(
+            &mut self.simulation,
+            &mut self.snakes,
+            self.opponents
+                .iter_mut()
+                .enumerate()
+                .map(|(i, opponent)| (i + 1, opponent.as_mut())),
+        );
+    }
+
+    fn food_distance(&self) -> Option<u32> {
+        let snakes: Vec<&Snake> = self.snakes.iter().collect();
+        let view = BoardView::new(&self.simulation, &snakes, 0);
+        let head = *view.me().snake.front().unwrap();
+        view.pellets()
+            .into_iter()
+            .map(|pellet| distance(view.board, head, pellet))
+            .min()
+    }
+}
+
+fn level(config: &EnvConfig) -> Level {
+    config
+        .level
+        .clone()
+        .unwrap_or_else(|| Level::open(&config.board))
+}
+
+fn start(
+    config: &EnvConfig,
+    seed: GameSeed,
+) -> (Simulation, Vec<Snake>, Vec<Box<dyn SnakeController>>) {
+    let (simulation, snakes) = Simulation::multiplayer(
+        &level(config),
+        config.board.wall_mode,
+        config.mode.clone(),
+        seed,
+        1 + config.opponents,
+        config.win_condition,
+    );
+    let opponents = (0..config.opponents)
+        .map(|_| Box::new(AiController::new(config.difficulty)) as Box<dyn SnakeController>)
+        .collect();
+    (simulation, snakes, opponents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every observation and reward from an episode played with the same actions.
+    fn episode(env: &mut SnakeEnv, seed: u64) -> Vec<(Vec<f32>, Vec<f32>, f32)> {
+        let first = env.reset(seed);
+        let mut steps = vec![(first.grid, first.features, 0.0)];
+        for &action in ALL_DIRECTIONS.iter().cycle().take(120) {
+            let (observation, reward, done, _) = env.step(action);
+            steps.push((observation.grid, observation.features, reward));
+            if done {
+                break;
+            }
+        }
+        steps
+    }
+
+    #[test]
+    fn same_seed_and_actions_play_out_the_same() {
+        let config = EnvConfig {
+            opponents: 2,
+            rewards: RewardShaping {
+                closer: 0.1,
+                ..RewardShaping::default()
+            },
+            ..EnvConfig::default()
+        };
+        let mut env = SnakeEnv::new(config).unwrap();
+        let first = episode(&mut env, 11);
+        assert!(first.len() > 1);
+        assert_eq!(first, episode(&mut env, 11));
+    }
+
+    #[test]
+    fn finished_episodes_stay_finished() {
+        let mut env = SnakeEnv::new(EnvConfig::default()).unwrap();
+        env.reset(0);
+        //Heading straight up the open field runs into the top wall
+        let mut done = false;
+        while !done {
+            done = env.step(Direction::Up).2;
+        }
+        let (_, reward, done, info) = env.step(Direction::Left);
+        assert!(done);
+        assert_eq!(reward, 0.0);
+        assert_eq!(info.state, GameState::HitWall);
+    }
+
+    #[test]
+    fn unplayable_configs_are_errors() {
+        let crowded = EnvConfig {
+            opponents: MAX_PLAYERS,
+            ..EnvConfig::default()
+        };
+        assert!(matches!(
+            SnakeEnv::new(crowded),
+            Err(EnvError::TooManyOpponents(_))
+        ));
+
+        let mut level = Level::open(&BoardConfig::default());
+        level.walls.push(level.spawn);
+        let walled = EnvConfig {
+            level: Some(level),
+            ..EnvConfig::default()
+        };
+        assert!(matches!(SnakeEnv::new(walled), Err(EnvError::Level(_))));
+    }
+
+    #[test]
+    fn food_offsets_go_the_short_way_round_on_wrapping_boards() {
+        let board = BoardConfig {
+            wall_mode: WallMode::Wrap,
+            ..BoardConfig::default()
+        };
+        //A single pellet near the left edge, level with the head in the middle
+        let mut level = Level::open(&board);
+        level.spawn = (board.width - 3, board.height / 2);
+        level.food = vec![(1, board.height / 2)];
+        let config = EnvConfig {
+            board,
+            level: Some(level),
+            ..EnvConfig::default()
+        };
+        let features = SnakeEnv::new(config).unwrap().reset(0).features;
+        let dx = features[2 * ALL_DIRECTIONS.len()];
+        assert!(
+            (dx - 4.0 / board.width as f32).abs() < 1e-6,
+            "dx was {}",
+            dx
+        );
+    }
+}
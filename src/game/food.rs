@@ -1,39 +1,257 @@
-use amethyst::core::math::Point2;
-use rand::{thread_rng, Rng};
-use std::collections::HashSet;
+use nalgebra::Point2;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+};
 
-use crate::game::defines::*;
-use crate::game::Snake;
+use crate::game::{defines::*, BoardConfig, Snake};
 
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum PelletKind {
+    Normal,
+    Golden,
+    Shrink,
+    Speed,
+    Slow,
+}
+
+impl PelletKind {
+    pub fn points(&self) -> u32 {
+        match self {
+            PelletKind::Golden => GOLDEN_POINTS,
+            _ => 1,
+        }
+    }
+
+    pub fn sprite(&self) -> usize {
+        match self {
+            PelletKind::Normal => 1,
+            PelletKind::Golden => 17,
+            PelletKind::Shrink => 18,
+            PelletKind::Speed => 19,
+            PelletKind::Slow => 20,
+        }
+    }
+}
+
+// A pellet that only sticks around for a while, worth less the longer it is left.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BonusPellet {
+    pub point: Point2<u32>,
+    pub ticks_left: u32,
+}
+
+impl BonusPellet {
+    pub fn points(&self) -> u32 {
+        (BONUS_MAX_POINTS * self.ticks_left / BONUS_LIFETIME_TICKS).max(1)
+    }
+
+    pub fn is_expiring(&self) -> bool {
+        self.ticks_left <= BONUS_BLINK_TICKS
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Food {
-    pub pellets: HashSet<Point2<u32>>,
+    pub pellets: HashMap<Point2<u32>, PelletKind>,
+    pub bonus: Option<BonusPellet>,
 }
 
 impl Food {
-    pub fn new() -> Self {
-        let mut pellets = HashSet::new();
-        pellets.insert(Point2::new(20, 5));
-        pellets.insert(Point2::new(20, 35));
-        pellets.insert(Point2::new(5, 20));
-        pellets.insert(Point2::new(35, 20));
-        Food { pellets }
+    pub fn new<I: IntoIterator<Item = Point2<u32>>>(pellets: I) -> Self {
+        Food {
+            pellets: pellets
+                .into_iter()
+                .map(|point| (point, PelletKind::Normal))
+                .collect(),
+            bonus: None,
+        }
     }
 
-    pub fn add_random_pellet(&mut self, snake: &Snake) {
-        let mut rand = thread_rng();
+    pub fn add_random_pellet<R: Rng, S: Borrow<Snake>>(
+        &mut self,
+        kind: PelletKind,
+        board: &BoardConfig,
+        walls: &HashSet<Point2<u32>>,
+        snakes: &[S],
+        rand: &mut R,
+    ) {
+        if let Some(point) = self.free_cell(board, walls, snakes, rand) {
+            self.pellets.insert(point, kind);
+        }
+    }
+
+    // Eats the bonus pellet if it is at the point, returning what it was worth.
+    pub fn eat_bonus(&mut self, point: Point2<u32>) -> Option<u32> {
+        match self.bonus.as_ref() {
+            Some(bonus) if bonus.point == point => {
+                let points = bonus.points();
+                self.bonus = None;
+                Some(points)
+            }
+            _ => None,
+        }
+    }
+
+    // Counts the bonus pellet down by a tick, or maybe spawns one if there isn't one out.
+    pub fn update_bonus<R: Rng, S: Borrow<Snake>>(
+        &mut self,
+        board: &BoardConfig,
+        walls: &HashSet<Point2<u32>>,
+        snakes: &[S],
+        rand: &mut R,
+    ) {
+        if let Some(bonus) = self.bonus.as_mut() {
+            bonus.ticks_left -= 1;
+            if bonus.ticks_left == 0 {
+                self.bonus = None;
+            }
+        } else if rand.gen_range(0, BONUS_SPAWN_ODDS) == 0 {
+            if let Some(point) = self.free_cell(board, walls, snakes, rand) {
+                self.bonus = Some(BonusPellet {
+                    point,
+                    ticks_left: BONUS_LIFETIME_TICKS,
+                });
+            }
+        }
+    }
+
+    fn free_cell<R: Rng, S: Borrow<Snake>>(
+        &self,
+        board: &BoardConfig,
+        walls: &HashSet<Point2<u32>>,
+        snakes: &[S],
+        rand: &mut R,
+    ) -> Option<Point2<u32>> {
+        let bonus_count = if self.bonus.is_some() { 1 } else { 0 };
 
         //Don't try to add in the screen is filled
-        if snake.snake.len() + self.pellets.len() == (GRID_SIZE * GRID_SIZE) as usize {
-            return;
+        let snake_cells: usize = snakes.iter().map(|snake| snake.borrow().snake.len()).sum();
+        if snake_cells + self.pellets.len() + bonus_count + walls.len() >= board.cell_count() {
+            return None;
         }
 
         loop {
-            let new_point = Point2::new(rand.gen_range(0, GRID_SIZE), rand.gen_range(0, GRID_SIZE));
+            let new_point = Point2::new(
+                rand.gen_range(0, board.width),
+                rand.gen_range(0, board.height),
+            );
 
-            if !self.pellets.contains(&new_point) && !snake.snake.contains(&new_point) {
-                self.pellets.insert(new_point);
+            if !self.pellets.contains_key(&new_point)
+                && !walls.contains(&new_point)
+                && !snakes
+                    .iter()
+                    .any(|snake| snake.borrow().snake.contains(&new_point))
+                && self.bonus.as_ref().map(|bonus| bonus.point) != Some(new_point)
+            {
+                return Some(new_point);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Direction, GameRng, GameSeed, WallMode};
+
+    fn bonus_at(x: u32, y: u32, ticks_left: u32) -> Food {
+        let mut food = Food::new(vec![]);
+        food.bonus = Some(BonusPellet {
+            point: Point2::new(x, y),
+            ticks_left,
+        });
+        food
+    }
+
+    #[test]
+    fn bonus_pellets_are_worth_less_as_they_age() {
+        let points = |ticks_left| {
+            BonusPellet {
+                point: Point2::new(0, 0),
+                ticks_left,
+            }
+            .points()
+        };
+        assert_eq!(points(BONUS_LIFETIME_TICKS), BONUS_MAX_POINTS);
+        assert_eq!(points(BONUS_LIFETIME_TICKS / 2), BONUS_MAX_POINTS / 2);
+        //Never worth nothing, however late it is eaten
+        assert_eq!(points(1), 1);
+    }
+
+    #[test]
+    fn only_the_bonus_cell_eats_the_bonus() {
+        let mut food = bonus_at(3, 4, BONUS_LIFETIME_TICKS);
+        assert_eq!(food.eat_bonus(Point2::new(4, 3)), None);
+        assert!(food.bonus.is_some());
+        assert_eq!(food.eat_bonus(Point2::new(3, 4)), Some(BONUS_MAX_POINTS));
+        assert!(food.bonus.is_none());
+        assert_eq!(food.eat_bonus(Point2::new(3, 4)), None);
+    }
+
+    #[test]
+    fn bonus_pellets_count_down_and_expire() {
+        let board = BoardConfig::default();
+        let snakes: Vec<Snake> = vec![];
+        let mut rng = GameRng::new(GameSeed(1));
+        let mut food = bonus_at(3, 4, BONUS_BLINK_TICKS + 1);
+        assert!(!food.bonus.as_ref().unwrap().is_expiring());
+
+        food.update_bonus(&board, &HashSet::new(), &snakes, &mut rng);
+        let bonus = food.bonus.as_ref().unwrap();
+        assert_eq!(bonus.ticks_left, BONUS_BLINK_TICKS);
+        assert!(bonus.is_expiring());
+
+        for _ in 0..BONUS_BLINK_TICKS {
+            assert!(food.bonus.is_some());
+            food.update_bonus(&board, &HashSet::new(), &snakes, &mut rng);
+        }
+        assert!(food.bonus.is_none());
+    }
+
+    #[test]
+    fn bonus_pellets_spawn_on_a_free_cell() {
+        //A 4x2 board with the snake along the top and walls and a pellet filling all but one cell
+        let board = BoardConfig {
+            width: 4,
+            height: 2,
+            wall_mode: WallMode::Solid,
+        };
+        let snakes = vec![Snake::new(&board, Point2::new(3, 0), Direction::Right)];
+        let walls: HashSet<_> = vec![Point2::new(0, 1), Point2::new(1, 1)]
+            .into_iter()
+            .collect();
+        let mut food = Food::new(vec![Point2::new(2, 1)]);
+        let taken = snakes[0].snake.len() + walls.len() + food.pellets.len();
+        assert_eq!(taken, board.cell_count() - 1, "the board isn't set up");
+
+        let mut rng = GameRng::new(GameSeed(2));
+        for _ in 0..BONUS_SPAWN_ODDS * 20 {
+            food.update_bonus(&board, &walls, &snakes, &mut rng);
+            if food.bonus.is_some() {
                 break;
             }
         }
+        let bonus = food.bonus.expect("no bonus pellet spawned");
+        assert_eq!(bonus.point, Point2::new(3, 1));
+        assert_eq!(bonus.ticks_left, BONUS_LIFETIME_TICKS);
+    }
+
+    #[test]
+    fn bonus_pellets_wait_for_room() {
+        let board = BoardConfig {
+            width: 2,
+            height: 1,
+            wall_mode: WallMode::Solid,
+        };
+        let snakes: Vec<Snake> = vec![];
+        let mut food = Food::new(vec![Point2::new(0, 0), Point2::new(1, 0)]);
+        let mut rng = GameRng::new(GameSeed(3));
+        for _ in 0..BONUS_SPAWN_ODDS * 20 {
+            food.update_bonus(&board, &HashSet::new(), &snakes, &mut rng);
+        }
+        assert!(food.bonus.is_none());
     }
 }
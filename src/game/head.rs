@@ -0,0 +1,60 @@
+use amethyst::{
+    core::transform::Transform,
+    ecs::prelude::*,
+    renderer::{palette::Srgba, SpriteRender},
+};
+
+use crate::game::{defines::*, Direction, Simulation, Snake, TickTimer};
+
+pub struct HeadRenderSystem;
+
+impl<'s> System<'s> for HeadRenderSystem {
+    type SystemData = (
+        ReadExpect<'s, Simulation>,
+        ReadExpect<'s, TickTimer>,
+        ReadStorage<'s, Snake>,
+        WriteStorage<'s, Transform>,
+        WriteStorage<'s, SpriteRender>,
+    );
+
+    fn run(&mut self, (simulation, timer, snakes, mut transforms, mut sprites): Self::SystemData) {
+        //Each snake's entity carries the sprite for its head
+        let alpha = timer.alpha();
+        for (snake, transform, sprite) in (&snakes, &mut transforms, &mut sprites).join() {
+            let cell = *snake.snake.front().unwrap();
+            let direction = snake.directions[0];
+
+            //Slide from the cell we came from to the current one, unless the snake has
+            //stopped for good
+            let alpha = if snake.is_alive() { alpha } else { 1.0 };
+            let (x, y) = simulation.board.cell_center(cell);
+            let (from_x, from_y) = match direction {
+                Direction::Up => (x, y - TILE_SIZE as f32),
+                Direction::Down => (x, y + TILE_SIZE as f32),
+                Direction::Left => (x + TILE_SIZE as f32, y),
+                Direction::Right => (x - TILE_SIZE as f32, y),
+            };
+
+            transform.set_translation_xyz(
+                from_x + (x - from_x) * alpha,
+                from_y + (y - from_y) * alpha,
+                1.0,
+            );
+            sprite.sprite_number = head_sprite(direction);
+        }
+    }
+}
+
+pub fn head_sprite(direction: Direction) -> usize {
+    match direction {
+        Direction::Down => 8,
+        Direction::Up => 9,
+        Direction::Right => 10,
+        Direction::Left => 11,
+    }
+}
+
+pub fn player_tint(player: usize) -> Srgba {
+    let [red, green, blue, alpha] = PLAYER_TINTS[player % MAX_PLAYERS];
+    Srgba::new(red, green, blue, alpha)
+}
@@ -0,0 +1,68 @@
+use serde::Serialize;
+
+use crate::game::{BoardView, GameSeed, GameState, Simulation, Snake, SnakeController};
+
+use std::borrow::BorrowMut;
+
+// How one game played without a window went for the first snake.
+#[derive(Serialize, Clone, Debug)]
+pub struct GameRecord {
+    pub seed: u64,
+    pub score: u32,
+    pub length: usize,
+    pub ticks: u32,
+    //Still Playing if the snake outlasted everyone else or the game was cut off
+    pub death: GameState,
+}
+
+impl GameRecord {
+    pub fn new(seed: GameSeed, simulation: &Simulation, snake: &Snake) -> Self {
+        GameRecord {
+            seed: seed.0,
+            score: snake.score.points,
+            length: snake.snake.len(),
+            ticks: simulation.ticks,
+            death: snake.state,
+        }
+    }
+}
+
+// Plays a game out as fast as it will go, each snake steered by the controller at its index,
+// giving up after max_ticks in case the controllers never finish it.
+pub fn play_headless(
+    simulation: &mut Simulation,
+    snakes: &mut [Snake],
+    controllers: &mut [Box<dyn SnakeController>],
+    max_ticks: u32,
+) {
+    while !simulation.is_over(snakes) && simulation.ticks < max_ticks {
+        steer_snakes(
+            simulation,
+            snakes,
+            controllers
+                .iter_mut()
+                .map(|controller| controller.as_mut())
+                .enumerate(),
+        );
+        simulation.tick(snakes);
+    }
+}
+
+// Lets each controller turn the snake of the player it's paired with, ready for the next
+// tick.
+pub fn steer_snakes<'a, S, C, I>(simulation: &mut Simulation, snakes: &mut [S], controllers: I)
+where
+    S: BorrowMut<Snake>,
+    C: SnakeController + ?Sized + 'a,
+    I: IntoIterator<Item = (usize, &'a mut C)>,
+{
+    for (player, controller) in controllers {
+        let choice = {
+            let view: Vec<&Snake> = snakes.iter().map(|snake| snake.borrow()).collect();
+            controller.steer(&BoardView::new(simulation, &view, player))
+        };
+        if let Some(direction) = choice {
+            simulation.turn(snakes, player, direction);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use std::{collections::BTreeMap, fmt, fs, io, path::PathBuf};
+
+use crate::game::{user_config_dir, BoardConfig, GameMode, GameSeed, Simulation, Snake};
+
+const HIGH_SCORE_COUNT: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: u32,
+    pub length: usize,
+    pub duration: f32,
+    pub date: String,
+    pub seed: u64,
+}
+
+impl HighScoreEntry {
+    // A finished run, still waiting on the player to put a name to it.
+    pub fn from_run(simulation: &Simulation, snake: &Snake, seed: GameSeed) -> Self {
+        HighScoreEntry {
+            name: String::new(),
+            score: snake.score.points,
+            length: snake.snake.len(),
+            duration: simulation.elapsed,
+            date: Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            seed: seed.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum HighScoreError {
+    Io(io::Error),
+    Write(ron::ser::Error),
+}
+
+impl fmt::Display for HighScoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HighScoreError::Io(e) => write!(f, "could not access high score file: {}", e),
+            HighScoreError::Write(e) => write!(f, "could not write high score file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for HighScoreError {}
+
+impl From<io::Error> for HighScoreError {
+    fn from(e: io::Error) -> Self {
+        HighScoreError::Io(e)
+    }
+}
+
+impl From<ron::ser::Error> for HighScoreError {
+    fn from(e: ron::ser::Error) -> Self {
+        HighScoreError::Write(e)
+    }
+}
+
+// The best runs for each game mode and board size, best first.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct HighScores {
+    tables: BTreeMap<String, Vec<HighScoreEntry>>,
+}
+
+impl HighScores {
+    pub fn table_key(mode: &GameMode, board: &BoardConfig) -> String {
+        format!("{} {}x{}", mode.name, board.width, board.height)
+    }
+
+    // A missing or broken file just means starting a fresh table.
+    pub fn load() -> Self {
+        let path = high_score_path();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => return HighScores::default(),
+        };
+        ron::de::from_str(&text).unwrap_or_else(|e| {
+            log::warn!("Ignoring high scores in {}: {}", path.display(), e);
+            HighScores::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), HighScoreError> {
+        let path = high_score_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.tables.keys().cloned().collect()
+    }
+
+    pub fn table(&self, key: &str) -> &[HighScoreEntry] {
+        self.tables.get(key).map(|t| t.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn qualifies(&self, key: &str, score: u32) -> bool {
+        let table = self.table(key);
+        score > 0
+            && (table.len() < HIGH_SCORE_COUNT || table.iter().any(|entry| score > entry.score))
+    }
+
+    pub fn insert(&mut self, key: &str, entry: HighScoreEntry) {
+        let table = self.tables.entry(key.to_string()).or_default();
+        //Ties go to whoever got there first
+        let index = table
+            .iter()
+            .position(|e| entry.score > e.score)
+            .unwrap_or(table.len());
+        table.insert(index, entry);
+        table.truncate(HIGH_SCORE_COUNT);
+    }
+}
+
+fn high_score_path() -> PathBuf {
+    user_config_dir().join("high_scores.ron")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u32) -> HighScoreEntry {
+        HighScoreEntry {
+            name: name.to_string(),
+            score,
+            length: 4,
+            duration: 10.0,
+            date: "2020-01-01 12:00".to_string(),
+            seed: 0,
+        }
+    }
+
+    fn names(high_scores: &HighScores, key: &str) -> Vec<String> {
+        high_scores
+            .table(key)
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn tables_are_kept_best_first_with_ties_to_the_earlier_run() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert("Classic 20x20", entry("a", 5));
+        high_scores.insert("Classic 20x20", entry("b", 9));
+        high_scores.insert("Classic 20x20", entry("c", 5));
+        high_scores.insert("Classic 20x20", entry("d", 7));
+        assert_eq!(names(&high_scores, "Classic 20x20"), ["b", "d", "a", "c"]);
+    }
+
+    #[test]
+    fn each_mode_and_board_size_has_its_own_table() {
+        let mode = GameMode::default();
+        let small = BoardConfig {
+            width: 10,
+            height: 10,
+            ..BoardConfig::default()
+        };
+        let small_key = HighScores::table_key(&mode, &small);
+        let default_key = HighScores::table_key(&mode, &BoardConfig::default());
+        assert_ne!(small_key, default_key);
+
+        let mut high_scores = HighScores::default();
+        high_scores.insert(&small_key, entry("a", 3));
+        assert_eq!(names(&high_scores, &small_key), ["a"]);
+        assert!(high_scores.table(&default_key).is_empty());
+        assert_eq!(high_scores.keys(), [small_key]);
+    }
+
+    #[test]
+    fn full_tables_only_take_better_runs() {
+        let mut high_scores = HighScores::default();
+        assert!(!high_scores.qualifies("key", 0), "scoreless runs qualified");
+        for score in 1..=HIGH_SCORE_COUNT as u32 {
+            assert!(high_scores.qualifies("key", score));
+            high_scores.insert("key", entry("old", score * 10));
+        }
+        assert!(
+            !high_scores.qualifies("key", 10),
+            "a tie with the worst run qualified"
+        );
+        assert!(high_scores.qualifies("key", 11));
+
+        high_scores.insert("key", entry("new", 11));
+        let table = high_scores.table("key");
+        assert_eq!(table.len(), HIGH_SCORE_COUNT);
+        assert_eq!(table.last().unwrap().name, "new");
+        assert!(table.iter().all(|entry| entry.score != 10));
+    }
+}
@@ -0,0 +1,73 @@
+use amethyst::{
+    ecs::prelude::*,
+    input::{InputHandler, StringBindings},
+};
+
+use crate::game::{defines::*, player_action, Direction, Pilot};
+
+const DIRECTION_ACTIONS: [(&str, Direction); 4] = [
+    ("move_up", Direction::Up),
+    ("move_down", Direction::Down),
+    ("move_left", Direction::Left),
+    ("move_right", Direction::Right),
+];
+
+// Reads every player's keys and stick, passing each press to the keyboard controllers
+// listening to that player. The MoveSystem steers with them as it ticks.
+#[derive(Default)]
+pub struct DirectionChangeSystem {
+    keys_held: [[bool; 4]; MAX_PLAYERS],
+    stick_held: [Option<Direction>; MAX_PLAYERS],
+}
+
+impl<'s> System<'s> for DirectionChangeSystem {
+    type SystemData = (
+        WriteStorage<'s, Pilot>,
+        Read<'s, InputHandler<StringBindings>>,
+    );
+
+    fn run(&mut self, (mut pilots, input): Self::SystemData) {
+        let mut presses = Vec::new();
+        for player in 0..MAX_PLAYERS {
+            //Only press when the action goes down, so holding a key doesn't flood the queue
+            for (held, (action, direction)) in self.keys_held[player]
+                .iter_mut()
+                .zip(DIRECTION_ACTIONS.iter())
+            {
+                let is_down = input
+                    .action_is_down(&player_action(player, action))
+                    .unwrap_or(false);
+                if is_down && !*held {
+                    presses.push((player, *direction));
+                }
+                *held = is_down;
+            }
+
+            //The stick works the same way, pressing once each time it is pushed a new way
+            let stick = Direction::from_stick(
+                input
+                    .axis_value(&player_action(player, "move_x"))
+                    .unwrap_or(0.0),
+                input
+                    .axis_value(&player_action(player, "move_y"))
+                    .unwrap_or(0.0),
+            );
+            if let Some(direction) = stick {
+                if self.stick_held[player] != stick {
+                    presses.push((player, direction));
+                }
+            }
+            self.stick_held[player] = stick;
+        }
+
+        for pilot in (&mut pilots).join() {
+            if let Pilot::Keyboard(keyboard) = pilot {
+                for &(player, direction) in presses.iter() {
+                    if keyboard.listens_to(player) {
+                        keyboard.press(direction);
+                    }
+                }
+            }
+        }
+    }
+}
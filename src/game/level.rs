@@ -0,0 +1,362 @@
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    ffi::OsStr,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::game::{defines::*, BoardConfig, Direction, WallMode};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Level {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub spawn: (u32, u32),
+    pub heading: Direction,
+    pub walls: Vec<(u32, u32)>,
+    pub food: Vec<(u32, u32)>,
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(std::io::Error),
+    Parse(ron::de::Error),
+    Write(ron::ser::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(e) => write!(f, "could not access level file: {}", e),
+            LevelError::Parse(e) => write!(f, "could not parse level file: {}", e),
+            LevelError::Write(e) => write!(f, "could not write level file: {}", e),
+            LevelError::Invalid(reason) => write!(f, "invalid level: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<std::io::Error> for LevelError {
+    fn from(e: std::io::Error) -> Self {
+        LevelError::Io(e)
+    }
+}
+
+impl From<ron::de::Error> for LevelError {
+    fn from(e: ron::de::Error) -> Self {
+        LevelError::Parse(e)
+    }
+}
+
+impl From<ron::ser::Error> for LevelError {
+    fn from(e: ron::ser::Error) -> Self {
+        LevelError::Write(e)
+    }
+}
+
+impl Level {
+    // The classic empty field with a pellet in each direction from the spawn.
+    pub fn open(board: &BoardConfig) -> Self {
+        let center = board.center();
+        Level {
+            name: "Open Field".to_string(),
+            width: board.width,
+            height: board.height,
+            spawn: (center.x, center.y),
+            heading: Direction::Up,
+            walls: Vec::new(),
+            food: vec![
+                (center.x, board.height / 8),
+                (center.x, board.height - 1 - board.height / 8),
+                (board.width / 8, center.y),
+                (board.width - 1 - board.width / 8, center.y),
+            ],
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LevelError> {
+        let file = fs::File::open(path)?;
+        let level: Level = ron::de::from_reader(file)?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), LevelError> {
+        self.validate()?;
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    // Every level file in a directory, sorted by file name. Broken files are skipped.
+    pub fn load_all<P: AsRef<Path>>(dir: P) -> Vec<Level> {
+        let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension() == Some(OsStr::new("ron")))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+
+        paths
+            .into_iter()
+            .filter_map(|path| match Level::load(&path) {
+                Ok(level) => Some(level),
+                Err(e) => {
+                    log::warn!("Skipping level {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn spawn_point(&self) -> Point2<u32> {
+        Point2::new(self.spawn.0, self.spawn.1)
+    }
+
+    // Where each player starts. Extra players are spread along the spawn row facing the
+    // same way, shuffled sideways and then onto the rows either side until their bodies are
+    // clear of the walls and each other.
+    pub fn spawn_points(&self, players: usize) -> Result<Vec<Point2<u32>>, LevelError> {
+        if players <= 1 {
+            return Ok(vec![self.spawn_point()]);
+        }
+
+        let (width, height) = (self.width as i64, self.height as i64);
+        let (column, row) = (self.spawn.0 as i64, self.spawn.1 as i64);
+        let gap = width / (players as i64 + 1);
+        let mut spawns = Vec::new();
+        let mut taken: Vec<Point2<u32>> = Vec::new();
+        for i in 0..players as i64 {
+            let wanted = column + gap * i - gap * (players as i64 - 1) / 2;
+            let free = (0..height)
+                .flat_map(|offset| vec![row - offset, row + offset])
+                .filter(|&y| y >= 0 && y < height)
+                .flat_map(|y| {
+                    (0..width)
+                        .flat_map(|offset| vec![wanted - offset, wanted + offset])
+                        .filter(|&x| x >= 0 && x < width)
+                        .map(move |x| Point2::new(x as u32, y as u32))
+                })
+                .filter_map(|point| self.start_body(point))
+                .find(|body| body.iter().all(|point| !taken.contains(point)));
+            match free {
+                Some(body) => {
+                    spawns.push(body[0]);
+                    taken.extend(body);
+                }
+                None => {
+                    return Err(LevelError::Invalid(format!(
+                        "no room for {} snakes to start",
+                        players
+                    )))
+                }
+            }
+        }
+        Ok(spawns)
+    }
+
+    // The cells a snake starting at a spawn covers, head first, or None if its body would
+    // run into a wall or off the board.
+    fn start_body(&self, spawn: Point2<u32>) -> Option<Vec<Point2<u32>>> {
+        let board = BoardConfig {
+            width: self.width,
+            height: self.height,
+            wall_mode: WallMode::Solid,
+        };
+        if spawn.x >= self.width || spawn.y >= self.height {
+            return None;
+        }
+        let mut body = vec![spawn];
+        while body.len() < START_LENGTH {
+            body.push(board.neighbour(*body.last().unwrap(), self.heading.opposite())?);
+        }
+        if body
+            .iter()
+            .any(|point| self.walls.contains(&(point.x, point.y)))
+        {
+            return None;
+        }
+        Some(body)
+    }
+
+    pub fn wall_points(&self) -> impl Iterator<Item = Point2<u32>> + '_ {
+        self.walls.iter().map(|&(x, y)| Point2::new(x, y))
+    }
+
+    pub fn food_points(&self) -> impl Iterator<Item = Point2<u32>> + '_ {
+        self.food.iter().map(|&(x, y)| Point2::new(x, y))
+    }
+
+    // Checks the level can be played by one snake. Games with more need spawn_points to find
+    // room for them as well.
+    pub fn validate(&self) -> Result<(), LevelError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(LevelError::Invalid("board has no cells".to_string()));
+        }
+
+        let in_bounds = |&(x, y): &(u32, u32)| x < self.width && y < self.height;
+        if !in_bounds(&self.spawn) {
+            return Err(LevelError::Invalid("spawn is off the board".to_string()));
+        }
+        if self.walls.contains(&self.spawn) {
+            return Err(LevelError::Invalid("spawn is inside a wall".to_string()));
+        }
+        if let Some(&(x, y)) = self.walls.iter().chain(&self.food).find(|p| !in_bounds(p)) {
+            return Err(LevelError::Invalid(format!(
+                "({}, {}) is off the board",
+                x, y
+            )));
+        }
+        if self.start_body(self.spawn_point()).is_none() {
+            return Err(LevelError::Invalid(
+                "snake would start in a wall or off the board".to_string(),
+            ));
+        }
+        for (i, food) in self.food.iter().enumerate() {
+            if self.walls.contains(food) {
+                return Err(LevelError::Invalid(format!(
+                    "food at ({}, {}) is inside a wall",
+                    food.0, food.1
+                )));
+            }
+            if *food == self.spawn {
+                return Err(LevelError::Invalid("food is on the spawn".to_string()));
+            }
+            if self.food[..i].contains(food) {
+                return Err(LevelError::Invalid(format!(
+                    "food at ({}, {}) is listed twice",
+                    food.0, food.1
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level() -> Level {
+        Level::open(&BoardConfig {
+            width: 10,
+            height: 10,
+            wall_mode: WallMode::Solid,
+        })
+    }
+
+    fn is_invalid(level: &Level) -> bool {
+        matches!(level.validate(), Err(LevelError::Invalid(_)))
+    }
+
+    #[test]
+    fn open_field_is_valid() {
+        assert!(level().validate().is_ok());
+    }
+
+    #[test]
+    fn walls_on_the_snake_are_invalid() {
+        let mut on_spawn = level();
+        on_spawn.walls.push(on_spawn.spawn);
+        assert!(is_invalid(&on_spawn));
+
+        //Heading up, so the body trails down below the spawn
+        let mut on_body = level();
+        on_body.walls.push((on_body.spawn.0, on_body.spawn.1 + 2));
+        assert!(is_invalid(&on_body));
+    }
+
+    #[test]
+    fn cells_off_the_board_are_invalid() {
+        let mut spawn = level();
+        spawn.spawn = (10, 3);
+        assert!(is_invalid(&spawn));
+
+        let mut wall = level();
+        wall.walls.push((3, 10));
+        assert!(is_invalid(&wall));
+
+        let mut food = level();
+        food.food.push((12, 0));
+        assert!(is_invalid(&food));
+
+        //Too near the bottom edge for the body to fit behind the head
+        let mut body = level();
+        body.spawn = (5, 8);
+        body.food.clear();
+        assert!(is_invalid(&body));
+
+        let mut empty = level();
+        empty.width = 0;
+        assert!(is_invalid(&empty));
+    }
+
+    #[test]
+    fn misplaced_food_is_invalid() {
+        let mut in_wall = level();
+        in_wall.walls.push(in_wall.food[0]);
+        assert!(is_invalid(&in_wall));
+
+        let mut on_spawn = level();
+        on_spawn.food.push(on_spawn.spawn);
+        assert!(is_invalid(&on_spawn));
+
+        let mut twice = level();
+        twice.food.push(twice.food[0]);
+        assert!(is_invalid(&twice));
+    }
+
+    #[test]
+    fn every_player_gets_a_clear_start() {
+        let level = level();
+        let board = BoardConfig {
+            width: level.width,
+            height: level.height,
+            wall_mode: WallMode::Solid,
+        };
+        let spawns = level.spawn_points(MAX_PLAYERS).unwrap();
+        assert_eq!(spawns.len(), MAX_PLAYERS);
+
+        let mut cells = Vec::new();
+        for spawn in spawns {
+            let body = level.start_body(spawn).unwrap();
+            assert_eq!(body.len(), START_LENGTH);
+            assert!(body
+                .iter()
+                .all(|point| point.x < board.width && point.y < board.height));
+            cells.extend(body);
+        }
+        for (i, cell) in cells.iter().enumerate() {
+            assert!(!cells[..i].contains(cell), "snakes overlap at {:?}", cell);
+        }
+    }
+
+    #[test]
+    fn cramped_levels_fit_one_snake() {
+        //Walls everywhere but seven cells of the column the one snake starts in, one short
+        //of room for two
+        let mut cramped = level();
+        cramped.food.clear();
+        let (column, row) = cramped.spawn;
+        cramped.walls = (0..cramped.width)
+            .flat_map(|x| (0..cramped.height).map(move |y| (x, y)))
+            .filter(|&(x, y)| x != column || y + 3 < row || y > row + 3)
+            .collect();
+        assert!(cramped.validate().is_ok());
+        assert_eq!(
+            cramped.spawn_points(1).unwrap(),
+            vec![cramped.spawn_point()]
+        );
+        assert!(matches!(
+            cramped.spawn_points(2),
+            Err(LevelError::Invalid(_))
+        ));
+    }
+}
@@ -1,15 +1,75 @@
+mod ai;
+#[cfg(feature = "engine")]
 mod audio;
+mod autopilot;
+#[cfg(feature = "engine")]
+mod bindings;
+mod board;
+mod controller;
 pub mod defines;
 mod direction;
+mod environment;
 mod food;
+#[cfg(feature = "engine")]
+mod head;
+mod headless;
+mod high_scores;
+#[cfg(feature = "engine")]
+mod input;
+mod level;
+mod mode;
+#[cfg(feature = "engine")]
 mod movement;
+mod replay;
+mod rng;
+mod save;
+mod score;
+mod settings;
+mod simulation;
 mod snake;
+mod tick;
+#[cfg(feature = "engine")]
 mod tile;
+mod user_dir;
 
+pub use self::ai::{choose_direction, AiController, Difficulty};
+#[cfg(feature = "engine")]
 pub use self::audio::play_eat_sound;
+#[cfg(feature = "engine")]
 pub use self::audio::AudioHandles;
-pub use self::direction::{Direction, DirectionChangeSystem};
-pub use self::food::Food;
+pub use self::autopilot::{AutopilotController, Tour};
+#[cfg(feature = "engine")]
+pub use self::bindings::{
+    action_key_name, default_bindings, describe_action, describe_combo, device_bindings,
+    load_bindings, player_action, player_actions, rebind, save_bindings,
+};
+pub use self::board::{BoardConfig, WallMode};
+pub use self::controller::{BoardView, BotRegistry, Controller, Pilot, SnakeController};
+pub use self::direction::{Direction, KeyboardController, ALL_DIRECTIONS};
+pub use self::environment::{
+    EnvConfig, EnvError, Observation, RewardShaping, SnakeEnv, StepInfo, FEATURE_COUNT, GRID_CHANNELS,
+};
+pub use self::food::{Food, PelletKind};
+#[cfg(feature = "engine")]
+pub use self::head::{head_sprite, player_tint, HeadRenderSystem};
+pub use self::headless::{play_headless, steer_snakes, GameRecord};
+pub use self::high_scores::{HighScoreEntry, HighScores};
+#[cfg(feature = "engine")]
+pub use self::input::DirectionChangeSystem;
+pub use self::level::{Level, LevelError};
+pub use self::mode::GameMode;
+#[cfg(feature = "engine")]
 pub use self::movement::MoveSystem;
-pub use self::snake::Snake;
+pub use self::replay::Replay;
+pub use self::rng::{GameRng, GameSeed};
+pub use self::save::SaveGame;
+pub use self::score::Score;
+pub use self::settings::{Setting, Settings, ALL_SETTINGS};
+pub use self::simulation::{GameState, Simulation, StepOutcome, WinCondition};
+#[cfg(feature = "engine")]
+pub use self::snake::world_snakes;
+pub use self::snake::{in_player_order, Snake, SnakeId};
+pub use self::tick::TickTimer;
+#[cfg(feature = "engine")]
 pub use self::tile::SnakeGameTile;
+pub use self::user_dir::{timestamped_path, user_config_dir};
@@ -0,0 +1,342 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use std::{fmt, fs, path::Path};
+
+use crate::game::PelletKind;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FoodWeights {
+    pub normal: u32,
+    pub golden: u32,
+    pub shrink: u32,
+    pub speed: u32,
+    pub slow: u32,
+}
+
+impl Default for FoodWeights {
+    fn default() -> Self {
+        FoodWeights {
+            normal: 20,
+            golden: 3,
+            shrink: 2,
+            speed: 2,
+            slow: 2,
+        }
+    }
+}
+
+impl FoodWeights {
+    pub fn choose<R: Rng>(&self, rand: &mut R) -> PelletKind {
+        //Weights too big to add up are turned away when the mode is loaded
+        let total = match self.total() {
+            Some(total) if total > 0 => total,
+            _ => return PelletKind::Normal,
+        };
+
+        let mut roll = rand.gen_range(0, total);
+        for &(kind, weight) in self.choices().iter() {
+            if roll < weight {
+                return kind;
+            }
+            roll -= weight;
+        }
+        PelletKind::Normal
+    }
+
+    // All the weights added up, or None if that's more than fits in a u32.
+    pub fn total(&self) -> Option<u32> {
+        self.choices()
+            .iter()
+            .try_fold(0u32, |total, &(_, weight)| total.checked_add(weight))
+    }
+
+    fn choices(&self) -> [(PelletKind, u32); 5] {
+        [
+            (PelletKind::Normal, self.normal),
+            (PelletKind::Golden, self.golden),
+            (PelletKind::Shrink, self.shrink),
+            (PelletKind::Speed, self.speed),
+            (PelletKind::Slow, self.slow),
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SpeedBasis {
+    Length,
+    Time,
+}
+
+// How the tick period shortens as the game goes on. Each level takes `every` segments
+// grown or seconds played, depending on the basis.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SpeedCurve {
+    pub basis: SpeedBasis,
+    pub every: f32,
+    pub start_period: f32,
+    pub period_scale: f32,
+    pub min_period: f32,
+}
+
+impl Default for SpeedCurve {
+    fn default() -> Self {
+        SpeedCurve {
+            basis: SpeedBasis::Length,
+            every: 5.0,
+            start_period: 0.05,
+            period_scale: 0.92,
+            min_period: 0.04,
+        }
+    }
+}
+
+impl SpeedCurve {
+    // Speed levels start at 1 and stop climbing once the period hits the floor.
+    pub fn level(&self, grown: usize, elapsed: f32) -> u32 {
+        let progress = match self.basis {
+            SpeedBasis::Length => grown as f32,
+            SpeedBasis::Time => elapsed,
+        };
+        let level = if self.every > 0.0 {
+            (progress / self.every) as u32
+        } else {
+            0
+        };
+        level.min(self.max_level()) + 1
+    }
+
+    pub fn period(&self, level: u32) -> f32 {
+        let period = self.start_period * self.period_scale.powi(level as i32 - 1);
+        period.max(self.min_period)
+    }
+
+    // Every period has to be a positive time, and every level has to take some growing or
+    // playing to reach.
+    fn validate(&self) -> Result<(), GameModeError> {
+        let positive = [
+            ("start_period", self.start_period),
+            ("min_period", self.min_period),
+            ("period_scale", self.period_scale),
+            ("every", self.every),
+        ];
+        for &(name, value) in positive.iter() {
+            if !(value > 0.0 && value.is_finite()) {
+                return Err(GameModeError::Invalid(format!(
+                    "{} has to be above 0, not {}",
+                    name, value
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn max_level(&self) -> u32 {
+        if self.period_scale >= 1.0 || self.start_period <= self.min_period {
+            return 0;
+        }
+        ((self.min_period / self.start_period).ln() / self.period_scale.ln()).ceil() as u32
+    }
+}
+
+// The rules that aren't tied to a particular board or level.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GameMode {
+    pub name: String,
+    pub food_weights: FoodWeights,
+    #[serde(default)]
+    pub speed_curve: SpeedCurve,
+}
+
+impl Default for GameMode {
+    fn default() -> Self {
+        GameMode {
+            name: "Classic".to_string(),
+            food_weights: FoodWeights::default(),
+            speed_curve: SpeedCurve::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GameModeError {
+    Io(std::io::Error),
+    Parse(ron::de::Error),
+    //The food weights add up to more than a u32 can hold
+    WeightOverflow,
+    //A speed curve the game couldn't keep time with
+    Invalid(String),
+}
+
+impl fmt::Display for GameModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameModeError::Io(e) => write!(f, "could not read game mode file: {}", e),
+            GameModeError::Parse(e) => write!(f, "could not parse game mode file: {}", e),
+            GameModeError::WeightOverflow => write!(f, "food weights add up to too much"),
+            GameModeError::Invalid(reason) => write!(f, "invalid game mode: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for GameModeError {}
+
+impl From<std::io::Error> for GameModeError {
+    fn from(e: std::io::Error) -> Self {
+        GameModeError::Io(e)
+    }
+}
+
+impl From<ron::de::Error> for GameModeError {
+    fn from(e: ron::de::Error) -> Self {
+        GameModeError::Parse(e)
+    }
+}
+
+impl GameMode {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, GameModeError> {
+        let file = fs::File::open(path)?;
+        let mode: GameMode = ron::de::from_reader(file)?;
+        if mode.food_weights.total().is_none() {
+            return Err(GameModeError::WeightOverflow);
+        }
+        mode.speed_curve.validate()?;
+        Ok(mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameRng, GameSeed};
+
+    fn weights(normal: u32, golden: u32) -> FoodWeights {
+        FoodWeights {
+            normal,
+            golden,
+            shrink: 0,
+            speed: 0,
+            slow: 0,
+        }
+    }
+
+    #[test]
+    fn zero_weights_give_normal_pellets() {
+        let mut rng = GameRng::new(GameSeed(1));
+        for _ in 0..100 {
+            assert_eq!(weights(0, 0).choose(&mut rng), PelletKind::Normal);
+        }
+    }
+
+    #[test]
+    fn only_weighted_kinds_are_chosen() {
+        let mut rng = GameRng::new(GameSeed(1));
+        for _ in 0..100 {
+            assert_eq!(weights(0, 5).choose(&mut rng), PelletKind::Golden);
+        }
+    }
+
+    #[test]
+    fn skewed_weights_skew_the_choice() {
+        let mut rng = GameRng::new(GameSeed(1));
+        let golden = (0..1000)
+            .filter(|_| weights(9, 1).choose(&mut rng) == PelletKind::Golden)
+            .count();
+        assert!(golden > 50 && golden < 150, "{} golden pellets", golden);
+    }
+
+    #[test]
+    fn overflowing_weights_have_no_total() {
+        assert_eq!(weights(u32::MAX, 0).total(), Some(u32::MAX));
+        assert_eq!(weights(u32::MAX, 1).total(), None);
+        let mut rng = GameRng::new(GameSeed(1));
+        assert_eq!(weights(u32::MAX, 1).choose(&mut rng), PelletKind::Normal);
+    }
+
+    fn curve(basis: SpeedBasis) -> SpeedCurve {
+        SpeedCurve {
+            basis,
+            every: 5.0,
+            start_period: 0.1,
+            period_scale: 0.5,
+            min_period: 0.02,
+        }
+    }
+
+    #[test]
+    fn levels_climb_with_growth_or_time() {
+        let by_length = curve(SpeedBasis::Length);
+        assert_eq!(by_length.level(0, 100.0), 1);
+        assert_eq!(by_length.level(4, 0.0), 1);
+        assert_eq!(by_length.level(5, 0.0), 2);
+        let by_time = curve(SpeedBasis::Time);
+        assert_eq!(by_time.level(100, 0.0), 1);
+        assert_eq!(by_time.level(0, 10.0), 3);
+    }
+
+    #[test]
+    fn levels_stop_at_the_period_floor() {
+        //0.1, 0.05, 0.025 and then the 0.02 floor
+        let curve = curve(SpeedBasis::Length);
+        assert_eq!(curve.level(1000, 0.0), 4);
+        assert!((curve.period(1) - 0.1).abs() < 1e-6);
+        assert!((curve.period(2) - 0.05).abs() < 1e-6);
+        assert!((curve.period(4) - 0.02).abs() < 1e-6);
+        assert!((curve.period(10) - 0.02).abs() < 1e-6);
+    }
+
+    #[test]
+    fn flat_curves_stay_on_the_first_level() {
+        let mut unscaled = curve(SpeedBasis::Length);
+        unscaled.period_scale = 1.0;
+        assert_eq!(unscaled.level(1000, 0.0), 1);
+        let mut stalled = curve(SpeedBasis::Length);
+        stalled.every = 0.0;
+        assert_eq!(stalled.level(1000, 0.0), 1);
+    }
+
+    #[test]
+    fn shipped_mode_matches_the_default_curve() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/config/game_mode.ron");
+        let shipped = GameMode::load(path).unwrap().speed_curve;
+        let default = SpeedCurve::default();
+        assert_eq!(shipped.start_period, default.start_period);
+        assert_eq!(shipped.min_period, default.min_period);
+    }
+
+    fn load(text: &str, name: &str) -> Result<GameMode, GameModeError> {
+        let path = std::env::temp_dir().join(format!("{}_{}.ron", name, std::process::id()));
+        fs::write(&path, text).unwrap();
+        let mode = GameMode::load(&path);
+        fs::remove_file(&path).unwrap();
+        mode
+    }
+
+    #[test]
+    fn curves_that_cannot_keep_time_are_rejected() {
+        let with_curve = |every: f32, start_period: f32, min_period: f32| {
+            format!(
+                "(name: \"Broken\", food_weights: (normal: 1, golden: 0, shrink: 0, speed: 0, \
+                 slow: 0), speed_curve: (basis: Length, every: {:?}, start_period: {:?}, \
+                 period_scale: 0.9, min_period: {:?}))",
+                every, start_period, min_period
+            )
+        };
+        let curves = [
+            ("start_period_zero", 5.0, 0.0, 0.04),
+            ("min_period_negative", 5.0, 0.05, -1.0),
+            ("every_zero", 0.0, 0.05, 0.04),
+        ];
+        for &(name, every, start_period, min_period) in curves.iter() {
+            assert!(
+                matches!(
+                    load(&with_curve(every, start_period, min_period), name),
+                    Err(GameModeError::Invalid(_))
+                ),
+                "{} loaded",
+                name
+            );
+        }
+        assert!(load(&with_curve(5.0, 0.05, 0.04), "curve_fine").is_ok());
+    }
+}
@@ -0,0 +1,305 @@
+use serde::{Deserialize, Serialize};
+
+use std::{
+    borrow::BorrowMut,
+    ffi::OsStr,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::game::{
+    defines::*, timestamped_path, user_config_dir, Direction, GameMode, GameSeed, Level,
+    LevelError, Simulation, Snake, StepOutcome, WallMode, WinCondition,
+};
+
+const MAX_REPLAYS: usize = 20;
+
+// Everything needed to play a game back: how it started, and every turn with the tick it
+// was made on and the player who made it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Replay {
+    pub level: Level,
+    pub wall_mode: WallMode,
+    pub mode: GameMode,
+    pub seed: GameSeed,
+    pub players: usize,
+    pub win_condition: WinCondition,
+    pub turns: Vec<(u32, usize, Direction)>,
+    pub end_tick: u32,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Parse(ron::de::Error),
+    Write(ron::ser::Error),
+    //The game it starts from couldn't be played
+    Invalid(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "could not access replay file: {}", e),
+            ReplayError::Parse(e) => write!(f, "could not parse replay file: {}", e),
+            ReplayError::Write(e) => write!(f, "could not write replay file: {}", e),
+            ReplayError::Invalid(reason) => write!(f, "invalid replay: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+impl From<ron::de::Error> for ReplayError {
+    fn from(e: ron::de::Error) -> Self {
+        ReplayError::Parse(e)
+    }
+}
+
+impl From<ron::ser::Error> for ReplayError {
+    fn from(e: ron::ser::Error) -> Self {
+        ReplayError::Write(e)
+    }
+}
+
+impl Replay {
+    pub fn new(
+        level: Level,
+        wall_mode: WallMode,
+        mode: GameMode,
+        seed: GameSeed,
+        players: usize,
+        win_condition: WinCondition,
+    ) -> Self {
+        Replay {
+            level,
+            wall_mode,
+            mode,
+            seed,
+            players,
+            win_condition,
+            turns: Vec::new(),
+            end_tick: 0,
+        }
+    }
+
+    pub fn start(&self) -> (Simulation, Vec<Snake>) {
+        Simulation::multiplayer(
+            &self.level,
+            self.wall_mode,
+            self.mode.clone(),
+            self.seed,
+            self.players,
+            self.win_condition,
+        )
+    }
+
+    // Runs the next tick, feeding in the turns that were made before it.
+    pub fn step<S: BorrowMut<Snake>>(
+        &self,
+        simulation: &mut Simulation,
+        snakes: &mut [S],
+    ) -> Vec<StepOutcome> {
+        let tick = simulation.ticks;
+        let first = self.turns.partition_point(|&(t, _, _)| t < tick);
+        for &(_, player, direction) in self.turns[first..]
+            .iter()
+            .take_while(|&&(t, _, _)| t == tick)
+        {
+            simulation.turn(snakes, player, direction);
+        }
+        simulation.tick(snakes)
+    }
+
+    // Replays from the start up to a tick, stopping early if the game ends first.
+    pub fn seek(&self, tick: u32) -> (Simulation, Vec<Snake>) {
+        let (mut simulation, mut snakes) = self.start();
+        let tick = tick.min(self.end_tick);
+        while simulation.ticks < tick && !simulation.is_over(&snakes) {
+            self.step(&mut simulation, &mut snakes);
+        }
+        (simulation, snakes)
+    }
+
+    // Reads a replay, turning away any whose game couldn't be started again.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ReplayError> {
+        let file = fs::File::open(path)?;
+        let replay: Replay = ron::de::from_reader(file)?;
+        replay.validate()?;
+        Ok(replay)
+    }
+
+    fn validate(&self) -> Result<(), ReplayError> {
+        if self.players == 0 || self.players > MAX_PLAYERS {
+            return Err(ReplayError::Invalid(format!(
+                "games have 1 to {} players, not {}",
+                MAX_PLAYERS, self.players
+            )));
+        }
+        let invalid = |e: LevelError| ReplayError::Invalid(e.to_string());
+        self.level.validate().map_err(invalid)?;
+        self.level.spawn_points(self.players).map_err(invalid)?;
+        Ok(())
+    }
+
+    // Writes the replay next to the others, dropping the oldest ones past the limit.
+    pub fn save(&self) -> Result<PathBuf, ReplayError> {
+        let dir = replay_dir();
+        fs::create_dir_all(&dir)?;
+        let path = timestamped_path(&dir, "replay");
+        fs::write(&path, ron::ser::to_string(self)?)?;
+
+        //The replay is already written, so failing to tidy up after it isn't worth reporting
+        for old in Replay::list().into_iter().skip(MAX_REPLAYS) {
+            if let Err(e) = fs::remove_file(&old) {
+                log::warn!("Could not remove old replay {}: {}", old.display(), e);
+            }
+        }
+        Ok(path)
+    }
+
+    // Every replay file, newest first.
+    pub fn list() -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = match fs::read_dir(replay_dir()) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension() == Some(OsStr::new("ron")))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+        paths.reverse();
+        paths
+    }
+}
+
+fn replay_dir() -> PathBuf {
+    user_config_dir().join("replays")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{
+        steer_snakes, AiController, BoardConfig, Difficulty, GameState, SnakeController,
+    };
+
+    // What can be seen of each snake and the food, to compare a replayed game with a live one.
+    type Snapshot = (Vec<(Vec<(u32, u32)>, GameState, u32)>, Vec<(u32, u32)>);
+
+    fn snapshot(simulation: &Simulation, snakes: &[Snake]) -> Snapshot {
+        let snakes = snakes
+            .iter()
+            .map(|snake| {
+                let body = snake.snake.iter().map(|point| (point.x, point.y)).collect();
+                (body, snake.state, snake.score.points)
+            })
+            .collect();
+        let mut food: Vec<(u32, u32)> = simulation
+            .food
+            .pellets
+            .keys()
+            .map(|point| (point.x, point.y))
+            .collect();
+        food.sort();
+        (snakes, food)
+    }
+
+    // A game between two computer snakes, with a snapshot after every tick.
+    fn live_game() -> (Replay, Vec<Snapshot>) {
+        let level = Level::open(&BoardConfig::default());
+        let (mut simulation, mut snakes) = Simulation::multiplayer(
+            &level,
+            WallMode::Solid,
+            GameMode::default(),
+            GameSeed(3),
+            2,
+            WinCondition::HighestScore,
+        );
+        let mut controllers = [
+            AiController::new(Difficulty::Hard),
+            AiController::new(Difficulty::Easy),
+        ];
+        let mut snapshots = vec![snapshot(&simulation, &snakes)];
+        while !simulation.is_over(&snakes) && simulation.ticks < 300 {
+            steer_snakes(
+                &mut simulation,
+                &mut snakes,
+                controllers
+                    .iter_mut()
+                    .map(|controller| controller as &mut dyn SnakeController)
+                    .enumerate(),
+            );
+            simulation.tick(&mut snakes);
+            snapshots.push(snapshot(&simulation, &snakes));
+        }
+        (simulation.replay(), snapshots)
+    }
+
+    // Writes a replay out to a file of its own and loads it back in.
+    fn round_trip(replay: &Replay, name: &str) -> Result<Replay, ReplayError> {
+        let path = std::env::temp_dir().join(format!("{}_{}.ron", name, std::process::id()));
+        fs::write(&path, ron::ser::to_string(replay).unwrap()).unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+        loaded
+    }
+
+    #[test]
+    fn replays_survive_a_round_trip() {
+        let (replay, _) = live_game();
+        let loaded = round_trip(&replay, "replay_round_trip").unwrap();
+
+        assert_eq!(loaded.seed, replay.seed);
+        assert_eq!(loaded.players, replay.players);
+        assert_eq!(loaded.turns, replay.turns);
+        assert_eq!(loaded.end_tick, replay.end_tick);
+    }
+
+    #[test]
+    fn replays_that_cannot_start_are_turned_away() {
+        let (replay, _) = live_game();
+        let crowded = Replay {
+            players: MAX_PLAYERS + 1,
+            ..replay.clone()
+        };
+        assert!(matches!(
+            round_trip(&crowded, "replay_crowded"),
+            Err(ReplayError::Invalid(_))
+        ));
+
+        let mut walled = replay;
+        walled.level.walls.push(walled.level.spawn);
+        assert!(matches!(
+            round_trip(&walled, "replay_walled"),
+            Err(ReplayError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn seeking_matches_the_live_game() {
+        let (replay, snapshots) = live_game();
+        assert!(!replay.turns.is_empty());
+        let ticks = [0, 1, 50, 150, replay.end_tick];
+        for tick in ticks
+            .iter()
+            .cloned()
+            .filter(|&tick| tick <= replay.end_tick)
+        {
+            let (simulation, snakes) = replay.seek(tick);
+            assert_eq!(simulation.ticks, tick);
+            assert_eq!(
+                snapshot(&simulation, &snakes),
+                snapshots[tick as usize],
+                "tick {}",
+                tick
+            );
+        }
+    }
+}
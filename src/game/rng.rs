@@ -0,0 +1,99 @@
+use rand::{RngCore, SeedableRng};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct GameSeed(pub u64);
+
+impl GameSeed {
+    pub fn random() -> Self {
+        GameSeed(rand::random())
+    }
+}
+
+// All randomness in a game goes through this, so the same seed and inputs replay the same game.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GameRng {
+    rng: Pcg32,
+}
+
+impl GameRng {
+    pub fn new(seed: GameSeed) -> Self {
+        GameRng {
+            rng: Pcg32::seed_from_u64(seed.0),
+        }
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        BoardConfig, GameMode, GameSeed, Level, Simulation, WallMode, ALL_DIRECTIONS,
+    };
+
+    // Every pellet on the board after each tick of a game steered by the same turns.
+    fn pellet_history(seed: GameSeed) -> Vec<Vec<(u32, u32)>> {
+        let board = BoardConfig {
+            wall_mode: WallMode::Wrap,
+            ..BoardConfig::default()
+        };
+        let (mut simulation, mut snakes) = Simulation::from_level(
+            &Level::open(&board),
+            board.wall_mode,
+            GameMode::default(),
+            seed,
+        );
+        let mut history = Vec::new();
+        for tick in 0..200 {
+            if tick % 9 == 0 {
+                let direction = ALL_DIRECTIONS[tick / 9 % ALL_DIRECTIONS.len()];
+                simulation.turn(&mut snakes, 0, direction);
+            }
+            simulation.tick(&mut snakes);
+            let mut pellets: Vec<(u32, u32)> = simulation
+                .food
+                .pellets
+                .keys()
+                .map(|point| (point.x, point.y))
+                .collect();
+            pellets.extend(
+                simulation
+                    .food
+                    .bonus
+                    .as_ref()
+                    .map(|bonus| (bonus.point.x, bonus.point.y)),
+            );
+            pellets.sort();
+            history.push(pellets);
+        }
+        history
+    }
+
+    #[test]
+    fn same_seed_and_inputs_place_the_same_pellets() {
+        assert_eq!(pellet_history(GameSeed(7)), pellet_history(GameSeed(7)));
+    }
+
+    #[test]
+    fn different_seeds_place_different_pellets() {
+        assert_ne!(pellet_history(GameSeed(7)), pellet_history(GameSeed(8)));
+    }
+}
@@ -0,0 +1,227 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    ffi::OsStr,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::game::{
+    timestamped_path, user_config_dir, Controller, GameSeed, Simulation, Snake, TickTimer,
+};
+
+// Bump this whenever a saved type changes meaning, so old saves are turned away.
+const SAVE_VERSION: u32 = 4;
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    pub date: String,
+    pub seed: GameSeed,
+    pub simulation: Simulation,
+    pub snakes: Vec<Snake>,
+    //What was steering each snake, so computer players stay computer players
+    pub controllers: Vec<Controller>,
+    pub timer: TickTimer,
+    //Whether the autopilot has steered at all, which keeps the run off the high scores
+    #[serde(default)]
+    pub autopilot_used: bool,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Parse(ron::de::Error),
+    Write(ron::ser::Error),
+    Version(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "could not access save file: {}", e),
+            SaveError::Parse(e) => write!(f, "could not parse save file: {}", e),
+            SaveError::Write(e) => write!(f, "could not write save file: {}", e),
+            SaveError::Version(version) => write!(
+                f,
+                "save file is version {}, expected {}",
+                version, SAVE_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<ron::de::Error> for SaveError {
+    fn from(e: ron::de::Error) -> Self {
+        SaveError::Parse(e)
+    }
+}
+
+impl From<ron::ser::Error> for SaveError {
+    fn from(e: ron::ser::Error) -> Self {
+        SaveError::Write(e)
+    }
+}
+
+impl SaveGame {
+    pub fn new(
+        seed: GameSeed,
+        simulation: Simulation,
+        snakes: Vec<Snake>,
+        controllers: Vec<Controller>,
+        timer: TickTimer,
+    ) -> Self {
+        SaveGame {
+            version: SAVE_VERSION,
+            date: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            seed,
+            simulation,
+            snakes,
+            controllers,
+            timer,
+            autopilot_used: false,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SaveError> {
+        let file = fs::File::open(path)?;
+        let save: SaveGame = ron::de::from_reader(file)?;
+        if save.version != SAVE_VERSION {
+            return Err(SaveError::Version(save.version));
+        }
+        Ok(save)
+    }
+
+    // Writes a new save file named after the time it was made, returning its path.
+    pub fn save(&self) -> Result<PathBuf, SaveError> {
+        let dir = save_dir();
+        fs::create_dir_all(&dir)?;
+        let path = timestamped_path(&dir, "save");
+        let text = ron::ser::to_string(self)?;
+        fs::write(&path, text)?;
+        Ok(path)
+    }
+
+    // Every save that can still be loaded, newest first.
+    pub fn list() -> Vec<SaveGame> {
+        let mut paths: Vec<PathBuf> = match fs::read_dir(save_dir()) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension() == Some(OsStr::new("ron")))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+        paths.reverse();
+
+        paths
+            .into_iter()
+            .filter_map(|path| match SaveGame::load(&path) {
+                Ok(save) => Some(save),
+                Err(e) => {
+                    log::warn!("Skipping save {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+fn save_dir() -> PathBuf {
+    user_config_dir().join("saves")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{
+        steer_snakes, AiController, BoardConfig, Difficulty, GameMode, Level, WallMode,
+    };
+
+    // Plays a computer snake on for some ticks.
+    fn play(simulation: &mut Simulation, snakes: &mut [Snake], ticks: u32) {
+        let mut controller = AiController::new(Difficulty::Hard);
+        for _ in 0..ticks {
+            if simulation.is_over(snakes) {
+                break;
+            }
+            steer_snakes(simulation, snakes, Some((0, &mut controller)));
+            simulation.tick(snakes);
+        }
+    }
+
+    // Writes a save out to a file of its own and loads it back in.
+    fn round_trip(save: &SaveGame, name: &str) -> Result<SaveGame, SaveError> {
+        let path = std::env::temp_dir().join(format!("{}_{}.ron", name, std::process::id()));
+        fs::write(&path, ron::ser::to_string(save).unwrap()).unwrap();
+        let loaded = SaveGame::load(&path);
+        fs::remove_file(&path).unwrap();
+        loaded
+    }
+
+    // A game a computer snake has been playing for a while.
+    fn started_game() -> SaveGame {
+        let (mut simulation, mut snakes) = Simulation::from_level(
+            &Level::open(&BoardConfig::default()),
+            WallMode::Solid,
+            GameMode::default(),
+            GameSeed(5),
+        );
+        play(&mut simulation, &mut snakes, 60);
+        SaveGame::new(
+            GameSeed(5),
+            simulation,
+            snakes,
+            vec![Controller::Ai(Difficulty::Hard)],
+            TickTimer::new(0.1),
+        )
+    }
+
+    #[test]
+    fn loaded_games_play_on_the_same() {
+        let mut save = started_game();
+        save.autopilot_used = true;
+        let mut loaded = round_trip(&save, "save_round_trip").unwrap();
+
+        assert_eq!(loaded.seed, save.seed);
+        assert_eq!(loaded.controllers, save.controllers);
+        assert_eq!(loaded.timer.period, save.timer.period);
+        assert!(loaded.autopilot_used);
+        assert_eq!(loaded.simulation.ticks, save.simulation.ticks);
+
+        //The food and its randomness come back too, so both games carry on alike
+        play(&mut save.simulation, &mut save.snakes, 200);
+        play(&mut loaded.simulation, &mut loaded.snakes, 200);
+        assert_eq!(loaded.simulation.ticks, save.simulation.ticks);
+        for (loaded, snake) in loaded.snakes.iter().zip(&save.snakes) {
+            assert_eq!(loaded.snake, snake.snake);
+            assert_eq!(loaded.state, snake.state);
+            assert_eq!(loaded.score.points, snake.score.points);
+        }
+        let mut loaded_food: Vec<_> = loaded.simulation.food.pellets.keys().collect();
+        let mut food: Vec<_> = save.simulation.food.pellets.keys().collect();
+        loaded_food.sort_by_key(|point| (point.x, point.y));
+        food.sort_by_key(|point| (point.x, point.y));
+        assert_eq!(loaded_food, food);
+    }
+
+    #[test]
+    fn saves_from_another_version_are_turned_away() {
+        let mut save = started_game();
+        save.version = SAVE_VERSION - 1;
+        match round_trip(&save, "save_old_version") {
+            Err(SaveError::Version(version)) => assert_eq!(version, SAVE_VERSION - 1),
+            Err(e) => panic!("wrong error: {}", e),
+            Ok(_) => panic!("an old save loaded"),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug)]
+pub struct Score {
+    pub points: u32,
+}
+
+impl Score {
+    pub fn add(&mut self, points: u32) {
+        self.points += points;
+    }
+}
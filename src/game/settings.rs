@@ -0,0 +1,437 @@
+use serde::{Deserialize, Serialize};
+
+use std::{fmt, fs, io, path::PathBuf};
+
+use crate::game::{defines::*, user_config_dir, BoardConfig, Difficulty, WallMode, WinCondition};
+
+const RESOLUTIONS: [(u32, u32); 5] = [
+    (1024, 1024),
+    (1280, 1280),
+    (1600, 1600),
+    (1280, 720),
+    (1920, 1080),
+];
+
+// The player's choices, picked up by each new game.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub game_speed: f32,
+    pub board: BoardConfig,
+    pub fullscreen: bool,
+    pub resolution: (u32, u32),
+    pub players: usize,
+    pub win_condition: WinCondition,
+    //Computer snakes joining the players, as many as there is room for
+    pub opponents: usize,
+    pub difficulty: Difficulty,
+    //A registered bot the computer snakes play as instead, by name
+    pub opponent_bot: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            game_speed: 1.0,
+            board: BoardConfig::default(),
+            fullscreen: false,
+            resolution: (1280, 1280),
+            players: 1,
+            win_condition: WinCondition::default(),
+            opponents: 0,
+            difficulty: Difficulty::default(),
+            opponent_bot: None,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Setting {
+    MasterVolume,
+    SfxVolume,
+    GameSpeed,
+    BoardWidth,
+    BoardHeight,
+    WallMode,
+    WindowMode,
+    Resolution,
+    Players,
+    WinCondition,
+    Opponents,
+    Difficulty,
+}
+
+// The settings on the settings screen. The match setup is picked before each game instead.
+pub const ALL_SETTINGS: [Setting; 8] = [
+    Setting::MasterVolume,
+    Setting::SfxVolume,
+    Setting::GameSpeed,
+    Setting::BoardWidth,
+    Setting::BoardHeight,
+    Setting::WallMode,
+    Setting::WindowMode,
+    Setting::Resolution,
+];
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    Write(ron::ser::Error),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(e) => write!(f, "could not access settings file: {}", e),
+            SettingsError::Write(e) => write!(f, "could not write settings file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<io::Error> for SettingsError {
+    fn from(e: io::Error) -> Self {
+        SettingsError::Io(e)
+    }
+}
+
+impl From<ron::ser::Error> for SettingsError {
+    fn from(e: ron::ser::Error) -> Self {
+        SettingsError::Write(e)
+    }
+}
+
+impl Settings {
+    // A missing or broken file falls back to the defaults.
+    pub fn load() -> Self {
+        let path = settings_path();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => return Settings::default(),
+        };
+        Settings::parse(&text).unwrap_or_else(|e| {
+            log::warn!("Ignoring settings in {}: {}", path.display(), e);
+            Settings::default()
+        })
+    }
+
+    // Reads settings, pulling anything out of range back within what the settings screen
+    // allows.
+    fn parse(text: &str) -> Result<Self, ron::de::Error> {
+        let mut settings: Settings = ron::de::from_str(text)?;
+        let defaults = Settings::default();
+        for &setting in [
+            Setting::MasterVolume,
+            Setting::SfxVolume,
+            Setting::GameSpeed,
+        ]
+        .iter()
+        {
+            let (_, min, max) = slider_range(setting).unwrap();
+            let value = settings.slider_value(setting).unwrap();
+            let value = if value.is_finite() {
+                value.clamp(min, max)
+            } else {
+                defaults.slider_value(setting).unwrap()
+            };
+            settings.set_slider_value(setting, value);
+        }
+        settings.board.width = settings.board.width.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+        settings.board.height = settings.board.height.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+        if !RESOLUTIONS.contains(&settings.resolution) {
+            settings.resolution = defaults.resolution;
+        }
+        settings.players = settings.players.clamp(1, MAX_PLAYERS);
+        settings.opponents = settings.opponents.min(MAX_PLAYERS - 1);
+        Ok(settings)
+    }
+
+    pub fn save(&self) -> Result<(), SettingsError> {
+        let path = settings_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    // The computer snakes that fit in a match alongside the players.
+    pub fn computers(&self) -> usize {
+        self.opponents.min(MAX_PLAYERS - self.players)
+    }
+
+    pub fn sfx_gain(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+
+    // Moves a setting one notch up or down, cycling the ones that are a list of choices.
+    pub fn adjust(&mut self, setting: Setting, up: bool) {
+        let step = |value: f32, by: f32, min: f32, max: f32| {
+            let value = if up { value + by } else { value - by };
+            //Round off so repeated steps don't drift away from the notches
+            ((value / by).round() * by).clamp(min, max)
+        };
+        let step_size = |value: u32| {
+            let value = if up {
+                value + 5
+            } else {
+                value.saturating_sub(5)
+            };
+            value.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE)
+        };
+
+        match setting {
+            Setting::MasterVolume | Setting::SfxVolume | Setting::GameSpeed => {
+                let (by, min, max) = slider_range(setting).unwrap();
+                let value = self.slider_value(setting).unwrap();
+                self.set_slider_value(setting, step(value, by, min, max));
+            }
+            Setting::BoardWidth => self.board.width = step_size(self.board.width),
+            Setting::BoardHeight => self.board.height = step_size(self.board.height),
+            Setting::WallMode => {
+                self.board.wall_mode = match self.board.wall_mode {
+                    WallMode::Solid => WallMode::Wrap,
+                    WallMode::Wrap => WallMode::Solid,
+                }
+            }
+            Setting::WindowMode => self.fullscreen = !self.fullscreen,
+            Setting::Resolution => {
+                let count = RESOLUTIONS.len();
+                let index = RESOLUTIONS
+                    .iter()
+                    .position(|&r| r == self.resolution)
+                    .unwrap_or(0);
+                let index = if up {
+                    (index + 1) % count
+                } else {
+                    (index + count - 1) % count
+                };
+                self.resolution = RESOLUTIONS[index];
+            }
+            Setting::Players => {
+                self.players = if up {
+                    self.players % MAX_PLAYERS + 1
+                } else {
+                    (self.players + MAX_PLAYERS - 2) % MAX_PLAYERS + 1
+                }
+            }
+            Setting::WinCondition => {
+                self.win_condition = match self.win_condition {
+                    WinCondition::LastSnakeStanding => WinCondition::HighestScore,
+                    WinCondition::HighestScore => WinCondition::LastSnakeStanding,
+                }
+            }
+            Setting::Opponents => {
+                self.opponents = if up {
+                    (self.opponents + 1) % MAX_PLAYERS
+                } else {
+                    (self.opponents + MAX_PLAYERS - 1) % MAX_PLAYERS
+                }
+            }
+            Setting::Difficulty => self.next_opponent(&[]),
+        }
+    }
+
+    // Steps the computer snakes through each difficulty and then each registered bot.
+    pub fn next_opponent(&mut self, bots: &[String]) {
+        let next_bot = match &self.opponent_bot {
+            Some(bot) => bots.iter().skip_while(|name| *name != bot).nth(1),
+            None if self.difficulty == Difficulty::Hard => bots.first(),
+            None => {
+                self.difficulty = self.difficulty.next();
+                return;
+            }
+        };
+        self.opponent_bot = next_bot.cloned();
+        if self.opponent_bot.is_none() {
+            self.difficulty = Difficulty::Easy;
+        }
+    }
+
+    // How far along its slider a setting is, from 0 to 1, for the settings that have one.
+    pub fn slider_position(&self, setting: Setting) -> Option<f32> {
+        let (_, min, max) = slider_range(setting)?;
+        Some((self.slider_value(setting)? - min) / (max - min))
+    }
+
+    // Sets a setting from a spot along its slider, snapped to the nearest notch.
+    pub fn slide(&mut self, setting: Setting, position: f32) {
+        if let Some((by, min, max)) = slider_range(setting) {
+            let value = min + position.clamp(0.0, 1.0) * (max - min);
+            self.set_slider_value(setting, ((value / by).round() * by).clamp(min, max));
+        }
+    }
+
+    fn slider_value(&self, setting: Setting) -> Option<f32> {
+        match setting {
+            Setting::MasterVolume => Some(self.master_volume),
+            Setting::SfxVolume => Some(self.sfx_volume),
+            Setting::GameSpeed => Some(self.game_speed),
+            _ => None,
+        }
+    }
+
+    fn set_slider_value(&mut self, setting: Setting, value: f32) {
+        match setting {
+            Setting::MasterVolume => self.master_volume = value,
+            Setting::SfxVolume => self.sfx_volume = value,
+            Setting::GameSpeed => self.game_speed = value,
+            _ => {}
+        }
+    }
+
+    pub fn describe(&self, setting: Setting) -> String {
+        let percent = |value: f32| (value * 100.0).round() as u32;
+        match setting {
+            Setting::MasterVolume => format!("Master Volume: {}%", percent(self.master_volume)),
+            Setting::SfxVolume => format!("SFX Volume: {}%", percent(self.sfx_volume)),
+            Setting::GameSpeed => format!("Game Speed: {}x", self.game_speed),
+            Setting::BoardWidth => format!("Board Width: {}", self.board.width),
+            Setting::BoardHeight => format!("Board Height: {}", self.board.height),
+            Setting::WallMode => format!("Walls: {}", self.board.wall_mode.name()),
+            Setting::WindowMode => format!(
+                "Window: {}",
+                if self.fullscreen {
+                    "Fullscreen"
+                } else {
+                    "Windowed"
+                }
+            ),
+            Setting::Resolution => {
+                format!("Resolution: {}x{}", self.resolution.0, self.resolution.1)
+            }
+            Setting::Players => format!("Players: {}", self.players),
+            Setting::WinCondition => format!("Win: {}", self.win_condition.name()),
+            Setting::Opponents => format!("Computer Snakes: {}", self.opponents),
+            Setting::Difficulty => match &self.opponent_bot {
+                Some(bot) => format!("Computer Difficulty: {}", bot),
+                None => format!("Computer Difficulty: {}", self.difficulty.name()),
+            },
+        }
+    }
+}
+
+// The notch size and limits of the settings that have a slider.
+fn slider_range(setting: Setting) -> Option<(f32, f32, f32)> {
+    match setting {
+        Setting::MasterVolume | Setting::SfxVolume => Some((0.1, 0.0, 1.0)),
+        Setting::GameSpeed => Some((0.25, 0.5, 2.0)),
+        _ => None,
+    }
+}
+
+fn settings_path() -> PathBuf {
+    user_config_dir().join("settings.ron")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_range_settings_are_clamped() {
+        let settings = Settings::parse(
+            "(
+                master_volume: 3.0,
+                sfx_volume: -1.0,
+                game_speed: 0.0,
+                board: (width: 0, height: 5000, wall_mode: Wrap),
+                resolution: (7, 7),
+                players: 9,
+                opponents: 12,
+            )",
+        )
+        .unwrap();
+        assert_eq!(settings.master_volume, 1.0);
+        assert_eq!(settings.sfx_volume, 0.0);
+        assert_eq!(settings.game_speed, 0.5);
+        assert_eq!(settings.board.width, MIN_BOARD_SIZE);
+        assert_eq!(settings.board.height, MAX_BOARD_SIZE);
+        assert_eq!(settings.board.wall_mode, WallMode::Wrap);
+        assert_eq!(settings.resolution, Settings::default().resolution);
+        assert_eq!(settings.players, MAX_PLAYERS);
+        assert_eq!(settings.opponents, MAX_PLAYERS - 1);
+
+        let settings = Settings::parse("(players: 0)").unwrap();
+        assert_eq!(settings.players, 1);
+    }
+
+    #[test]
+    fn sliders_step_by_notches_and_stop_at_their_ends() {
+        let mut settings = Settings::default();
+        settings.adjust(Setting::MasterVolume, true);
+        assert_eq!(settings.master_volume, 1.0);
+        for _ in 0..15 {
+            settings.adjust(Setting::MasterVolume, false);
+        }
+        assert_eq!(settings.master_volume, 0.0);
+        //Ten steps back up land on the top notch again, not just under it
+        for _ in 0..10 {
+            settings.adjust(Setting::MasterVolume, true);
+        }
+        assert_eq!(settings.master_volume, 1.0);
+
+        settings.adjust(Setting::GameSpeed, true);
+        assert_eq!(settings.game_speed, 1.25);
+        for _ in 0..10 {
+            settings.adjust(Setting::GameSpeed, false);
+        }
+        assert_eq!(settings.game_speed, 0.5);
+        assert_eq!(settings.master_volume, 1.0, "another slider moved");
+    }
+
+    #[test]
+    fn board_sizes_step_by_five_within_limits() {
+        let mut settings = Settings {
+            board: BoardConfig {
+                width: MIN_BOARD_SIZE,
+                height: MAX_BOARD_SIZE - 2,
+                ..BoardConfig::default()
+            },
+            ..Settings::default()
+        };
+        settings.adjust(Setting::BoardWidth, false);
+        assert_eq!(settings.board.width, MIN_BOARD_SIZE);
+        settings.adjust(Setting::BoardWidth, true);
+        assert_eq!(settings.board.width, MIN_BOARD_SIZE + 5);
+        settings.adjust(Setting::BoardHeight, true);
+        assert_eq!(settings.board.height, MAX_BOARD_SIZE);
+        settings.adjust(Setting::BoardHeight, false);
+        assert_eq!(settings.board.height, MAX_BOARD_SIZE - 5);
+    }
+
+    #[test]
+    fn choices_cycle_round_both_ways() {
+        let mut settings = Settings {
+            resolution: RESOLUTIONS[RESOLUTIONS.len() - 1],
+            ..Settings::default()
+        };
+        settings.adjust(Setting::Resolution, true);
+        assert_eq!(settings.resolution, RESOLUTIONS[0]);
+        settings.adjust(Setting::Resolution, false);
+        assert_eq!(settings.resolution, RESOLUTIONS[RESOLUTIONS.len() - 1]);
+
+        settings.adjust(Setting::Players, false);
+        assert_eq!(settings.players, MAX_PLAYERS);
+        settings.adjust(Setting::Players, true);
+        assert_eq!(settings.players, 1);
+
+        settings.adjust(Setting::Opponents, false);
+        assert_eq!(settings.opponents, MAX_PLAYERS - 1);
+        settings.adjust(Setting::Opponents, true);
+        assert_eq!(settings.opponents, 0);
+
+        settings.adjust(Setting::WallMode, true);
+        assert_eq!(settings.board.wall_mode, WallMode::Wrap);
+        settings.adjust(Setting::WallMode, false);
+        assert_eq!(settings.board.wall_mode, WallMode::Solid);
+        settings.adjust(Setting::WindowMode, true);
+        assert!(settings.fullscreen);
+    }
+}
@@ -0,0 +1,436 @@
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    borrow::{Borrow, BorrowMut},
+    collections::HashSet,
+};
+
+use crate::game::{
+    defines::*, BoardConfig, Direction, Food, GameMode, GameRng, GameSeed, Level, PelletKind,
+    Replay, Snake, WallMode,
+};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum GameState {
+    #[default]
+    Playing,
+    HitYourself,
+    HitWall,
+    //Ran into the body of the snake with this index
+    HitSnake(usize),
+    HeadOn,
+    //Filled every cell there was, so there's nowhere left to go
+    BoardFull,
+}
+
+// How a game with more than one snake is decided.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum WinCondition {
+    #[default]
+    LastSnakeStanding,
+    HighestScore,
+}
+
+impl WinCondition {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WinCondition::LastSnakeStanding => "Last Snake Standing",
+            WinCondition::HighestScore => "Highest Score",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum StepOutcome {
+    Moved,
+    Ate(u32),
+    Died(GameState),
+}
+
+// The snake rules without any engine resources, so the game can be run without a window.
+// The snakes themselves live apart from the board, as components while the game is on
+// screen, so every rule that needs them is handed them in player order.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Simulation {
+    pub board: BoardConfig,
+    pub walls: HashSet<Point2<u32>>,
+    pub food: Food,
+    pub mode: GameMode,
+    pub win_condition: WinCondition,
+    //Seconds of play, counted in ticks so it slows down with the game
+    pub elapsed: f32,
+    pub ticks: u32,
+    //Tick period scale from a speed or slow pellet, and how many ticks it has left
+    speed_effect: Option<(f32, u32)>,
+    rng: GameRng,
+    replay: Replay,
+}
+
+impl Simulation {
+    pub fn from_level(
+        level: &Level,
+        wall_mode: WallMode,
+        mode: GameMode,
+        seed: GameSeed,
+    ) -> (Self, Vec<Snake>) {
+        Self::multiplayer(level, wall_mode, mode, seed, 1, WinCondition::default())
+    }
+
+    // A fresh game and its snakes, one for each player. Levels that load or save only have
+    // room for one snake for sure, so this panics unless spawn_points finds room for the rest.
+    pub fn multiplayer(
+        level: &Level,
+        wall_mode: WallMode,
+        mode: GameMode,
+        seed: GameSeed,
+        players: usize,
+        win_condition: WinCondition,
+    ) -> (Self, Vec<Snake>) {
+        let board = BoardConfig {
+            width: level.width,
+            height: level.height,
+            wall_mode,
+        };
+        let snakes: Vec<Snake> = level
+            .spawn_points(players)
+            .expect("Level has no room for every player")
+            .into_iter()
+            .map(|spawn| Snake::new(&board, spawn, level.heading))
+            .collect();
+        //Pellets that would start out under a snake are left off
+        let food = level
+            .food_points()
+            .filter(|point| !snakes.iter().any(|snake| snake.snake.contains(point)));
+        let simulation = Simulation {
+            board,
+            walls: level.wall_points().collect(),
+            food: Food::new(food),
+            mode: mode.clone(),
+            win_condition,
+            elapsed: 0.0,
+            ticks: 0,
+            speed_effect: None,
+            rng: GameRng::new(seed),
+            replay: Replay::new(level.clone(), wall_mode, mode, seed, players, win_condition),
+        };
+        (simulation, snakes)
+    }
+
+    pub fn speed_level<S: Borrow<Snake>>(&self, snakes: &[S]) -> u32 {
+        let longest = snakes.iter().map(|snake| snake.borrow().snake.len()).max();
+        let grown = longest.unwrap_or(0).saturating_sub(START_LENGTH);
+        self.mode.speed_curve.level(grown, self.elapsed)
+    }
+
+    // Seconds between ticks, including any speed or slow pellet still in effect.
+    pub fn tick_period<S: Borrow<Snake>>(&self, snakes: &[S]) -> f32 {
+        let period = self.mode.speed_curve.period(self.speed_level(snakes));
+        match self.speed_effect {
+            Some((scale, _)) => period * scale,
+            None => period,
+        }
+    }
+
+    pub fn turn<S: BorrowMut<Snake>>(
+        &mut self,
+        snakes: &mut [S],
+        player: usize,
+        direction: Direction,
+    ) {
+        if self.is_over(snakes) {
+            return;
+        }
+        if let Some(snake) = snakes.get_mut(player) {
+            let snake = snake.borrow_mut();
+            if snake.is_alive() {
+                snake.queue_turn(direction);
+                self.replay.turns.push((self.ticks, player, direction));
+            }
+        }
+    }
+
+    // One snake plays until it dies. With more, last snake standing ends when one is left
+    // and highest score plays on until they are all gone.
+    pub fn is_over<S: Borrow<Snake>>(&self, snakes: &[S]) -> bool {
+        let alive = snakes
+            .iter()
+            .map(Borrow::borrow)
+            .filter(|snake: &&Snake| snake.is_alive())
+            .count();
+        match (snakes.len(), self.win_condition) {
+            (1, _) | (_, WinCondition::HighestScore) => alive == 0,
+            (_, WinCondition::LastSnakeStanding) => alive <= 1,
+        }
+    }
+
+    // Everyone tied for the win. Outlasting counts first for last snake standing, so snakes
+    // that all died on the same final tick share it.
+    pub fn winners<S: Borrow<Snake>>(&self, snakes: &[S]) -> Vec<usize> {
+        let rank = |snake: &Snake| match self.win_condition {
+            WinCondition::LastSnakeStanding => snake.died_at.unwrap_or(u32::MAX),
+            WinCondition::HighestScore => snake.score.points,
+        };
+        let best = snakes.iter().map(|snake| rank(snake.borrow())).max();
+        (0..snakes.len())
+            .filter(|&i| Some(rank(snakes[i].borrow())) == best)
+            .collect()
+    }
+
+    // The recording of this game so far.
+    pub fn replay(&self) -> Replay {
+        Replay {
+            end_tick: self.ticks,
+            ..self.replay.clone()
+        }
+    }
+
+    // Moves every snake at once, returning what happened to each.
+    pub fn tick<S: BorrowMut<Snake>>(&mut self, snakes: &mut [S]) -> Vec<StepOutcome> {
+        if self.is_over(snakes) {
+            return snakes
+                .iter()
+                .map(|snake| StepOutcome::Died(snake.borrow().state))
+                .collect();
+        }
+
+        self.elapsed += self.tick_period(snakes);
+        self.ticks += 1;
+
+        //Work out where every living snake is heading before any of them move
+        let mut targets = Vec::with_capacity(snakes.len());
+        for snake in snakes.iter_mut() {
+            let snake = snake.borrow_mut();
+            if !snake.is_alive() {
+                targets.push(None);
+                continue;
+            }
+            //Take at most one queued turn per tick
+            snake.take_turn();
+            let head = *snake.snake.front().unwrap();
+            targets.push(Some(self.board.neighbour(head, snake.direction)));
+        }
+
+        let mut deaths = vec![None; snakes.len()];
+        for (i, target) in targets.iter().enumerate() {
+            let new_point = match target {
+                Some(Some(point)) => *point,
+                Some(None) => {
+                    deaths[i] = Some(GameState::HitWall);
+                    continue;
+                }
+                None => continue,
+            };
+            let head = *snakes[i].borrow().snake.front().unwrap();
+
+            deaths[i] = if self.walls.contains(&new_point) {
+                Some(GameState::HitWall)
+            } else if targets.iter().enumerate().any(|(j, other)| {
+                //Two heads meeting in a cell, or passing through each other
+                j != i
+                    && (*other == Some(Some(new_point))
+                        || (*other == Some(Some(head))
+                            && snakes[j].borrow().snake.front() == Some(&new_point)))
+            }) {
+                Some(GameState::HeadOn)
+            } else if snakes[i].borrow().snake.contains(&new_point) {
+                Some(GameState::HitYourself)
+            } else {
+                snakes
+                    .iter()
+                    .position(|other| other.borrow().snake.contains(&new_point))
+                    .map(GameState::HitSnake)
+            };
+        }
+
+        for (snake, death) in snakes.iter_mut().zip(deaths) {
+            if let Some(state) = death {
+                let snake = snake.borrow_mut();
+                snake.state = state;
+                snake.died_at = Some(self.ticks);
+            }
+        }
+        if snakes.iter().all(|snake| !snake.borrow().is_alive()) {
+            return snakes
+                .iter()
+                .map(|snake| StepOutcome::Died(snake.borrow().state))
+                .collect();
+        }
+
+        if let Some((_, ticks_left)) = self.speed_effect.as_mut() {
+            *ticks_left -= 1;
+            if *ticks_left == 0 {
+                self.speed_effect = None;
+            }
+        }
+
+        let mut outcomes = Vec::with_capacity(snakes.len());
+        for (i, target) in targets.into_iter().enumerate() {
+            outcomes.push(match target {
+                Some(Some(new_point)) if snakes[i].borrow().is_alive() => {
+                    self.advance(snakes, i, new_point)
+                }
+                _ => StepOutcome::Died(snakes[i].borrow().state),
+            });
+        }
+
+        //A full board ends the game for everyone still going, as a win rather than a crash
+        let filled: usize = snakes.iter().map(|snake| snake.borrow().snake.len()).sum();
+        if filled + self.walls.len() >= self.board.cell_count() {
+            for snake in snakes.iter_mut() {
+                let snake = snake.borrow_mut();
+                if snake.is_alive() {
+                    snake.state = GameState::BoardFull;
+                    snake.died_at = Some(self.ticks);
+                }
+            }
+        }
+
+        self.food
+            .update_bonus(&self.board, &self.walls, snakes, &mut self.rng);
+        outcomes
+    }
+
+    fn advance<S: BorrowMut<Snake>>(
+        &mut self,
+        snakes: &mut [S],
+        player: usize,
+        new_point: Point2<u32>,
+    ) -> StepOutcome {
+        let eaten = self.food.pellets.remove(&new_point);
+        let bonus_points = self.food.eat_bonus(new_point);
+
+        let snake = snakes[player].borrow_mut();
+        snake.snake.push_front(new_point);
+        let direction = snake.direction;
+        snake.directions.push_front(direction);
+
+        let outcome = if let Some(kind) = eaten {
+            self.eat_pellet(snakes, player, kind)
+        } else if let Some(points) = bonus_points {
+            StepOutcome::Ate(points)
+        } else {
+            snake.snake.pop_back();
+            snake.directions.pop_back();
+            StepOutcome::Moved
+        };
+
+        if let StepOutcome::Ate(points) = outcome {
+            snakes[player].borrow_mut().score.add(points);
+        }
+        outcome
+    }
+
+    fn eat_pellet<S: BorrowMut<Snake>>(
+        &mut self,
+        snakes: &mut [S],
+        player: usize,
+        kind: PelletKind,
+    ) -> StepOutcome {
+        let next = self.mode.food_weights.choose(&mut self.rng);
+        self.food
+            .add_random_pellet(next, &self.board, &self.walls, snakes, &mut self.rng);
+
+        match kind {
+            PelletKind::Normal | PelletKind::Golden => {}
+            PelletKind::Shrink => {
+                //Don't grow from this one, and lose some tail on top
+                snakes[player].borrow_mut().shrink(SHRINK_SEGMENTS + 1);
+            }
+            PelletKind::Speed => {
+                self.speed_effect = Some((FAST_PERIOD_SCALE, SPEED_EFFECT_TICKS));
+            }
+            PelletKind::Slow => {
+                self.speed_effect = Some((SLOW_PERIOD_SCALE, SPEED_EFFECT_TICKS));
+            }
+        }
+        StepOutcome::Ate(kind.points())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two snakes facing each other along a row of an empty board, with their heads at the
+    // given columns.
+    fn facing(left: u32, right: u32, win_condition: WinCondition) -> (Simulation, Vec<Snake>) {
+        let board = BoardConfig {
+            width: 12,
+            height: 10,
+            wall_mode: WallMode::Solid,
+        };
+        let (mut simulation, _) = Simulation::multiplayer(
+            &Level::open(&board),
+            board.wall_mode,
+            GameMode::default(),
+            GameSeed(0),
+            2,
+            win_condition,
+        );
+        simulation.food = Food::new(std::iter::empty());
+        let snakes = vec![
+            Snake::new(&board, Point2::new(left, 5), Direction::Right),
+            Snake::new(&board, Point2::new(right, 5), Direction::Left),
+        ];
+        (simulation, snakes)
+    }
+
+    #[test]
+    fn heads_meeting_in_a_cell_crash() {
+        let (mut simulation, mut snakes) = facing(3, 5, WinCondition::LastSnakeStanding);
+        simulation.tick(&mut snakes);
+        assert_eq!(snakes[0].state, GameState::HeadOn);
+        assert_eq!(snakes[1].state, GameState::HeadOn);
+    }
+
+    #[test]
+    fn heads_swapping_cells_crash() {
+        let (mut simulation, mut snakes) = facing(3, 4, WinCondition::LastSnakeStanding);
+        simulation.tick(&mut snakes);
+        assert_eq!(snakes[0].state, GameState::HeadOn);
+        assert_eq!(snakes[1].state, GameState::HeadOn);
+    }
+
+    #[test]
+    fn running_into_a_body_blames_its_snake() {
+        let (mut simulation, mut snakes) = facing(3, 6, WinCondition::LastSnakeStanding);
+        //The second snake turns up out of the way, leaving its body across the first one's path
+        simulation.turn(&mut snakes, 1, Direction::Up);
+        for _ in 0..3 {
+            simulation.tick(&mut snakes);
+        }
+        assert_eq!(snakes[0].state, GameState::HitSnake(1));
+        assert!(snakes[1].is_alive());
+    }
+
+    #[test]
+    fn crashing_together_last_ties_for_the_win() {
+        let (mut simulation, mut snakes) = facing(3, 5, WinCondition::LastSnakeStanding);
+        simulation.tick(&mut snakes);
+        assert!(simulation.is_over(&snakes));
+        assert_eq!(simulation.winners(&snakes), vec![0, 1]);
+    }
+
+    #[test]
+    fn outlasting_wins_on_last_snake_standing() {
+        let (mut simulation, mut snakes) = facing(3, 6, WinCondition::LastSnakeStanding);
+        simulation.turn(&mut snakes, 1, Direction::Up);
+        while !simulation.is_over(&snakes) {
+            simulation.tick(&mut snakes);
+        }
+        assert_eq!(simulation.winners(&snakes), vec![1]);
+    }
+
+    #[test]
+    fn equal_scores_tie_on_highest_score() {
+        let (mut simulation, mut snakes) = facing(3, 5, WinCondition::HighestScore);
+        snakes[0].score.add(10);
+        snakes[1].score.add(10);
+        assert_eq!(simulation.winners(&snakes), vec![0, 1]);
+        snakes[1].score.add(1);
+        assert_eq!(simulation.winners(&snakes), vec![1]);
+        simulation.tick(&mut snakes);
+        assert!(simulation.is_over(&snakes));
+    }
+}
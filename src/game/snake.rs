@@ -1,30 +1,161 @@
-use crate::game::Direction;
-use amethyst::core::math::Point2;
+use crate::game::{defines::*, BoardConfig, Direction, GameState, Score};
+#[cfg(feature = "engine")]
+use amethyst::ecs::prelude::*;
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Snake {
     pub snake: VecDeque<Point2<u32>>,
     pub directions: VecDeque<Direction>,
     pub direction: Direction,
+    pub pending_turns: VecDeque<Direction>,
+    pub state: GameState,
+    //The tick this snake died on, for deciding who lasted longest
+    pub died_at: Option<u32>,
+    pub score: Score,
 }
 
-impl Default for Snake {
-    fn default() -> Self {
-        let mut snake = VecDeque::new();
-        snake.push_back(Point2::new(20, 20));
-        snake.push_back(Point2::new(20, 21));
-        snake.push_back(Point2::new(20, 22));
-        snake.push_back(Point2::new(20, 23));
+#[cfg(feature = "engine")]
+impl Component for Snake {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// Which player a snake entity belongs to, and so its place in the turn order.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SnakeId(pub usize);
+
+#[cfg(feature = "engine")]
+impl Component for SnakeId {
+    type Storage = DenseVecStorage<Self>;
+}
 
+// Sorts a join over snake entities into player order, the way the rules expect them.
+pub fn in_player_order<'a, T, I>(snakes: I) -> Vec<T>
+where
+    I: Iterator<Item = (&'a SnakeId, T)>,
+{
+    let mut snakes: Vec<(&SnakeId, T)> = snakes.collect();
+    snakes.sort_by_key(|(id, _)| id.0);
+    snakes.into_iter().map(|(_, snake)| snake).collect()
+}
+
+// A copy of every snake in the world, in player order.
+#[cfg(feature = "engine")]
+pub fn world_snakes(world: &World) -> Vec<Snake> {
+    let ids = world.read_storage::<SnakeId>();
+    let snakes = world.read_storage::<Snake>();
+    in_player_order((&ids, &snakes).join())
+        .into_iter()
+        .cloned()
+        .collect()
+}
+
+impl Snake {
+    pub fn new(board: &BoardConfig, spawn: Point2<u32>, heading: Direction) -> Self {
+        let mut snake = VecDeque::new();
         let mut directions = VecDeque::new();
-        directions.push_back(Direction::Up);
-        directions.push_back(Direction::Up);
-        directions.push_back(Direction::Up);
-        directions.push_back(Direction::Up);
+
+        //Lay the body out behind the head, cut short if it runs into the edge
+        let mut cell = Some(spawn);
+        while let Some(point) = cell {
+            if snake.len() == START_LENGTH || snake.contains(&point) {
+                break;
+            }
+            snake.push_back(point);
+            directions.push_back(heading);
+            cell = board.neighbour(point, heading.opposite());
+        }
+
         Snake {
             snake,
             directions,
-            direction: Direction::Up,
+            direction: heading,
+            pending_turns: VecDeque::new(),
+            state: GameState::Playing,
+            died_at: None,
+            score: Score::default(),
+        }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.state == GameState::Playing
+    }
+
+    pub fn queue_turn(&mut self, direction: Direction) {
+        if self.pending_turns.len() >= MAX_PENDING_TURNS {
+            return;
+        }
+
+        //Check against the heading this turn will follow, not just the current one
+        let follows = *self.pending_turns.back().unwrap_or(&self.direction);
+        if direction != follows && direction != follows.opposite() {
+            self.pending_turns.push_back(direction);
+        }
+    }
+
+    pub fn take_turn(&mut self) {
+        if let Some(direction) = self.pending_turns.pop_front() {
+            self.direction = direction;
+        }
+    }
+
+    // Drops segments off the tail, always leaving a head and tail behind.
+    pub fn shrink(&mut self, segments: usize) {
+        for _ in 0..segments {
+            if self.snake.len() <= MIN_SNAKE_LENGTH {
+                break;
+            }
+            self.snake.pop_back();
+            self.directions.pop_back();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snake() -> Snake {
+        let board = BoardConfig::default();
+        Snake::new(&board, board.center(), Direction::Up)
+    }
+
+    #[test]
+    fn reversing_is_ignored() {
+        let mut snake = snake();
+        snake.queue_turn(Direction::Down);
+        assert!(snake.pending_turns.is_empty());
+    }
+
+    #[test]
+    fn turns_are_checked_against_the_last_queued_one() {
+        let mut snake = snake();
+        snake.queue_turn(Direction::Left);
+        //Right would reverse the queued left, and Left again is no turn at all
+        snake.queue_turn(Direction::Right);
+        snake.queue_turn(Direction::Left);
+        snake.queue_turn(Direction::Down);
+        assert_eq!(snake.pending_turns, vec![Direction::Left, Direction::Down]);
+    }
+
+    #[test]
+    fn queue_is_capped() {
+        let mut snake = snake();
+        let turns = [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ];
+        for &direction in turns.iter().cycle().take(MAX_PENDING_TURNS + 3) {
+            snake.queue_turn(direction);
+        }
+        assert_eq!(snake.pending_turns.len(), MAX_PENDING_TURNS);
+
+        snake.take_turn();
+        assert_eq!(snake.direction, Direction::Left);
+        assert_eq!(snake.pending_turns.len(), MAX_PENDING_TURNS - 1);
+    }
+}
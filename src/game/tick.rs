@@ -0,0 +1,61 @@
+use crate::game::defines::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TickTimer {
+    pub period: f32,
+    accumulator: f32,
+}
+
+impl TickTimer {
+    pub fn new(period: f32) -> Self {
+        TickTimer {
+            period,
+            accumulator: 0.0,
+        }
+    }
+
+    // Returns how many simulation ticks have built up since the last frame.
+    pub fn advance(&mut self, delta_seconds: f32) -> u32 {
+        self.accumulator += delta_seconds;
+
+        let mut ticks = 0;
+        while self.accumulator >= self.period && ticks < MAX_CATCH_UP_TICKS {
+            self.accumulator -= self.period;
+            ticks += 1;
+        }
+
+        //After a long hitch drop the backlog rather than spiralling trying to catch up
+        if self.accumulator >= self.period {
+            self.accumulator %= self.period;
+        }
+        ticks
+    }
+
+    // How far between the last tick and the next one we are, from 0 to 1.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.period).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_build_up_across_frames() {
+        let mut timer = TickTimer::new(0.1);
+        assert_eq!(timer.advance(0.05), 0);
+        assert_eq!(timer.advance(0.1), 1);
+        assert!((timer.alpha() - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn catch_up_is_capped() {
+        let mut timer = TickTimer::new(0.1);
+        assert_eq!(timer.advance(10.0), MAX_CATCH_UP_TICKS);
+        //The rest of the hitch is dropped rather than carried into the next frame
+        assert!(timer.alpha() < 1.0);
+        assert_eq!(timer.advance(0.0), 0);
+    }
+}
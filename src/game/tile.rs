@@ -1,10 +1,14 @@
 use amethyst::{
-    core::math::{Point2, Point3},
+    core::{
+        math::{Point2, Point3},
+        Time,
+    },
     ecs::prelude::*,
+    renderer::palette::Srgba,
     tiles::Tile,
 };
 
-use crate::game::{Direction, Food, Snake};
+use crate::game::{defines::*, player_tint, Direction, Simulation, Snake, SnakeId};
 
 #[derive(Default, Clone)]
 pub struct SnakeGameTile;
@@ -13,17 +17,24 @@ impl Tile for SnakeGameTile {
         if point.z == 0 {
             Some(0)
         } else {
-            let snake = world.fetch::<Snake>();
-
-            let pos = snake
-                .snake
-                .iter()
-                .enumerate()
-                .find(|&(_, p)| p.x == point.x && p.y == point.y)
-                .map(|(loc, _)| loc);
+            let simulation = world.fetch::<Simulation>();
+            if simulation.walls.contains(&Point2::new(point.x, point.y)) {
+                return Some(16);
+            }
+            let snakes = world.read_storage::<Snake>();
+            let found = snakes.join().find_map(|snake| {
+                snake
+                    .snake
+                    .iter()
+                    .position(|p| p.x == point.x && p.y == point.y)
+                    .map(|loc| (snake, loc))
+            });
 
-            if let Some(p) = pos {
-                if p == snake.directions.len() - 1 {
+            if let Some((snake, p)) = found {
+                if p == 0 {
+                    //The head is drawn by its own sprite so it can slide between cells
+                    None
+                } else if p == snake.directions.len() - 1 {
                     match snake.directions[p - 1] {
                         Direction::Up => Some(12),
                         Direction::Down => Some(13),
@@ -54,14 +65,40 @@ impl Tile for SnakeGameTile {
                         _ => Some(1),
                     }
                 }
-            } else {
-                let food = world.fetch::<Food>();
-                if food.pellets.contains(&Point2::new(point.x, point.y)) {
-                    Some(1)
-                } else {
+            } else if let Some(bonus) = simulation
+                .food
+                .bonus
+                .as_ref()
+                .filter(|bonus| bonus.point == Point2::new(point.x, point.y))
+            {
+                //Blink the bonus pellet when it is about to run out
+                let time = world.fetch::<Time>();
+                let blink = (time.absolute_time_seconds() * BONUS_BLINKS_PER_SECOND * 2.0) as u64;
+                if bonus.is_expiring() && blink % 2 == 1 {
                     None
+                } else {
+                    Some(21)
                 }
+            } else {
+                simulation
+                    .food
+                    .pellets
+                    .get(&Point2::new(point.x, point.y))
+                    .map(|kind| kind.sprite())
             }
         }
     }
+
+    fn tint(&self, point: Point3<u32>, world: &World) -> Srgba {
+        let cell = Point2::new(point.x, point.y);
+        let ids = world.read_storage::<SnakeId>();
+        let snakes = world.read_storage::<Snake>();
+        match (&ids, &snakes)
+            .join()
+            .find(|(_, snake)| snake.snake.contains(&cell))
+        {
+            Some((id, _)) if point.z != 0 => player_tint(id.0),
+            _ => Srgba::new(1.0, 1.0, 1.0, 1.0),
+        }
+    }
 }
@@ -0,0 +1,21 @@
+use chrono::Local;
+
+use std::path::{Path, PathBuf};
+
+// Where per-player files like saves and high scores live, outside the install directory.
+pub fn user_config_dir() -> PathBuf {
+    dirs::config_dir().unwrap_or_default().join("SuperSnake")
+}
+
+// A new file in a directory named after the time, down to the millisecond, so the names
+// sort oldest first. A count is added if there's already a file with that name.
+pub fn timestamped_path(dir: &Path, prefix: &str) -> PathBuf {
+    let stamp = Local::now().format("%Y%m%d_%H%M%S_%3f");
+    let mut path = dir.join(format!("{}_{}.ron", prefix, stamp));
+    let mut count = 1;
+    while path.exists() {
+        count += 1;
+        path = dir.join(format!("{}_{}_{}.ron", prefix, stamp, count));
+    }
+    path
+}
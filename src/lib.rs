@@ -0,0 +1,11 @@
+pub mod game;
+#[cfg(feature = "engine")]
+pub mod states;
+
+#[cfg(feature = "engine")]
+mod app;
+mod batch;
+
+#[cfg(feature = "engine")]
+pub use crate::app::run;
+pub use crate::batch::simulate;
@@ -0,0 +1,284 @@
+use crate::game::{
+    default_bindings, defines::MAX_PLAYERS, describe_action, describe_combo, device_bindings,
+    player_actions, rebind, save_bindings,
+};
+use crate::states::SettingsState;
+use amethyst::{
+    assets::Loader,
+    core::ecs::prelude::*,
+    input::{Bindings, Button, InputEvent, InputHandler, StringBindings, VirtualKeyCode},
+    prelude::*,
+    ui::{Anchor, TtfFormat, UiButton, UiButtonBuilder, UiEventType, UiText, UiTransform},
+};
+
+pub struct BindingsState {
+    text_entity: Option<Entity>,
+    reset_button_entity: Option<UiButton>,
+    exit_button_entity: Option<UiButton>,
+    player_button_entity: Option<UiButton>,
+    player: usize,
+    actions: Vec<(String, &'static str)>,
+    //One button per action, clicked to start listening for its new key
+    rows: Vec<UiButton>,
+    //The row listening for a key and which of its keys gets replaced, counting one past the
+    //last for adding another
+    waiting_for_key: Option<(usize, usize)>,
+}
+
+impl BindingsState {
+    pub fn new() -> Self {
+        BindingsState {
+            text_entity: None,
+            reset_button_entity: None,
+            exit_button_entity: None,
+            player_button_entity: None,
+            player: 0,
+            actions: player_actions(0),
+            rows: Vec::new(),
+            waiting_for_key: None,
+        }
+    }
+
+    fn build_rows(&mut self, world: &mut World) {
+        let font = world.read_resource::<Loader>().load(
+            "Poppins-Black.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        for (i, &(_, label)) in self.actions.iter().enumerate() {
+            let button = UiButtonBuilder::<(), u32>::new(label)
+                .with_size(700.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(i as u32 + 3)
+                .with_position(0.0, 380.0 - 70.0 * i as f32)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1;
+            self.rows.push(button);
+        }
+    }
+
+    fn clear_rows(&mut self, world: &mut World) {
+        for button in self.rows.drain(..) {
+            world
+                .delete_entity(button.text_entity)
+                .expect("Failed to Delete Entity");
+            world
+                .delete_entity(button.image_entity)
+                .expect("Failed to Delete Entity");
+        }
+    }
+
+    // The keys bound to a row's action, any key standing in for the keyboard.
+    fn keys(&self, bindings: &Bindings<StringBindings>, row: usize) -> Vec<Vec<Button>> {
+        device_bindings(
+            bindings,
+            &self.actions[row].0,
+            &Button::Key(VirtualKeyCode::A),
+        )
+    }
+
+    fn refresh(&self, world: &World) {
+        let input = world.read_resource::<InputHandler<StringBindings>>();
+        let mut ui_texts = world.write_storage::<UiText>();
+        for (i, (button, (action, label))) in self.rows.iter().zip(self.actions.iter()).enumerate()
+        {
+            if let Some(text) = ui_texts.get_mut(button.text_entity) {
+                text.text =
+                    if let Some((_, slot)) = self.waiting_for_key.filter(|&(row, _)| row == i) {
+                        match self.keys(&input.bindings, i).get(slot) {
+                            Some(combo) => {
+                                format!(
+                                    "{}: Press a Key to Replace {}...",
+                                    label,
+                                    describe_combo(combo)
+                                )
+                            }
+                            None => format!("{}: Press a Key to Add...", label),
+                        }
+                    } else {
+                        format!("{}: {}", label, describe_action(&input.bindings, action))
+                    };
+            }
+        }
+        if let Some(text) =
+            ui_texts.get_mut(self.player_button_entity.as_ref().unwrap().text_entity)
+        {
+            text.text = format!("Player {}", self.player + 1);
+        }
+    }
+}
+
+impl SimpleState for BindingsState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        //Setup UI
+        let font = world.read_resource::<Loader>().load(
+            "Poppins-Black.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        let text_transform = UiTransform::new(
+            "Bindings Text".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            0.,
+            500.,
+            1.,
+            2000.,
+            50.,
+        );
+        self.text_entity = Some(
+            world
+                .create_entity()
+                .with(text_transform)
+                .with(UiText::new(
+                    font.clone(),
+                    "Key Bindings".to_string(),
+                    [1.0, 1.0, 1.0, 1.0],
+                    50.,
+                ))
+                .build(),
+        );
+
+        self.player_button_entity = Some(
+            UiButtonBuilder::<(), u32>::new("Player 1")
+                .with_size(200.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(2)
+                .with_position(0.0, 440.0)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1,
+        );
+
+        self.reset_button_entity = Some(
+            UiButtonBuilder::<(), u32>::new("Reset to Defaults")
+                .with_size(300.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(1)
+                .with_position(0.0, -300.0)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1,
+        );
+
+        self.exit_button_entity = Some(
+            UiButtonBuilder::<(), u32>::new("Save & Back")
+                .with_size(200.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(0)
+                .with_position(0.0, -400.0)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1,
+        );
+
+        self.build_rows(world);
+        self.refresh(world);
+    }
+
+    fn handle_event(&mut self, data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+        match event {
+            StateEvent::Ui(ui_event) => {
+                if ui_event.event_type == UiEventType::ClickStart {
+                    if ui_event.target == self.exit_button_entity.as_ref().unwrap().image_entity {
+                        let input = data.world.read_resource::<InputHandler<StringBindings>>();
+                        if let Err(e) = save_bindings(&input.bindings) {
+                            log::error!("Failed to save bindings: {}", e);
+                        }
+                        return Trans::Switch(Box::new(SettingsState::new()));
+                    }
+
+                    if ui_event.target == self.reset_button_entity.as_ref().unwrap().image_entity {
+                        data.world
+                            .write_resource::<InputHandler<StringBindings>>()
+                            .bindings = default_bindings();
+                        self.waiting_for_key = None;
+                    } else if ui_event.target
+                        == self.player_button_entity.as_ref().unwrap().image_entity
+                    {
+                        //Each player has their own steering, so swap the rows over
+                        self.player = (self.player + 1) % MAX_PLAYERS;
+                        self.actions = player_actions(self.player);
+                        self.waiting_for_key = None;
+                        self.clear_rows(data.world);
+                        self.build_rows(data.world);
+                    } else if let Some(i) = self
+                        .rows
+                        .iter()
+                        .position(|button| button.image_entity == ui_event.target)
+                    {
+                        //Clicking the same row again moves on to its next key
+                        let slot = match self.waiting_for_key {
+                            Some((row, slot)) if row == i => {
+                                let input =
+                                    data.world.read_resource::<InputHandler<StringBindings>>();
+                                (slot + 1) % (self.keys(&input.bindings, i).len() + 1)
+                            }
+                            _ => 0,
+                        };
+                        self.waiting_for_key = Some((i, slot));
+                    }
+                    self.refresh(data.world);
+                }
+            }
+            StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) => {
+                if let Some((i, slot)) = self.waiting_for_key.take() {
+                    let mut input = data.world.write_resource::<InputHandler<StringBindings>>();
+                    let replacing = self.keys(&input.bindings, i).into_iter().nth(slot);
+                    rebind(
+                        &mut input.bindings,
+                        &self.actions[i].0,
+                        replacing.as_deref(),
+                        Button::Key(key_code),
+                    );
+                    drop(input);
+                    self.refresh(data.world);
+                }
+            }
+            _ => {}
+        }
+
+        Trans::None
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        //Delete the text entity
+        data.world
+            .delete_entity(self.text_entity.unwrap())
+            .expect("Failed to Delete Entity");
+        self.clear_rows(data.world);
+        for button in self
+            .reset_button_entity
+            .take()
+            .into_iter()
+            .chain(self.exit_button_entity.take())
+            .chain(self.player_button_entity.take())
+        {
+            data.world
+                .delete_entity(button.text_entity)
+                .expect("Failed to Delete Entity");
+            data.world
+                .delete_entity(button.image_entity)
+                .expect("Failed to Delete Entity");
+        }
+    }
+}
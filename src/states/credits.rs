@@ -2,6 +2,7 @@ use crate::states::MainMenuState;
 use amethyst::{
     assets::Loader,
     core::ecs::prelude::*,
+    input::InputEvent,
     prelude::*,
     ui::{Anchor, TtfFormat, UiButton, UiButtonBuilder, UiEventType, UiText, UiTransform},
 };
@@ -78,6 +79,11 @@ impl SimpleState for CreditsState {
                     }
                 }
             }
+            StateEvent::Input(InputEvent::ActionPressed(action)) => {
+                if action == "back" {
+                    return Trans::Switch(Box::new(MainMenuState::new()));
+                }
+            }
             _ => {}
         }
 
@@ -1,24 +1,95 @@
-use crate::states::MainMenuState;
+use crate::game::{action_key_name, HighScoreEntry, HighScores, Replay};
+use crate::states::{HighScoresState, MainMenuState, ReplayState};
 use amethyst::{
     assets::Loader,
     core::ecs::prelude::*,
-    input::InputEvent,
+    input::{InputEvent, InputHandler, StringBindings, VirtualKeyCode},
     prelude::*,
     ui::{Anchor, TtfFormat, UiText, UiTransform},
+    winit::{Event, WindowEvent},
 };
 
+const MAX_NAME_LENGTH: usize = 12;
+
 pub struct GameOverState {
     reason_text: String,
+    //None when the run can't go on the high scores
+    table_key: Option<String>,
+    entry: HighScoreEntry,
+    replay: Replay,
+    //Only kept while a qualifying run is waiting for a name
+    high_scores: Option<HighScores>,
+    //Set once the name is on the table, so continuing shows it
+    saved_score: bool,
     big_text_entity: Option<Entity>,
     small_text_entity: Option<Entity>,
+    name_text_entity: Option<Entity>,
 }
 
 impl GameOverState {
-    pub fn new(reason_text: String) -> Self {
+    pub fn new(
+        reason_text: String,
+        table_key: Option<String>,
+        entry: HighScoreEntry,
+        replay: Replay,
+    ) -> Self {
         GameOverState {
             reason_text,
+            table_key,
+            entry,
+            replay,
+            high_scores: None,
+            saved_score: false,
             small_text_entity: None,
             big_text_entity: None,
+            name_text_entity: None,
+        }
+    }
+
+    fn name_text(&self) -> String {
+        format!("Name: {}_ - Press Enter to Save", self.entry.name)
+    }
+
+    fn replay_prompt(&self, world: &World) -> String {
+        let input = world.read_resource::<InputHandler<StringBindings>>();
+        format!(
+            "{} - Final Score {} - Press {} to Watch the Replay or {} to Continue",
+            self.reason_text,
+            self.entry.score,
+            action_key_name(&input.bindings, "replay"),
+            action_key_name(&input.bindings, "confirm")
+        )
+    }
+
+    // Puts the run on the table, then offers the replay like any other run.
+    fn save_high_score(&mut self, world: &World) {
+        let mut high_scores = self.high_scores.take().unwrap();
+        let table_key = self.table_key.clone().unwrap();
+        if self.entry.name.trim().is_empty() {
+            self.entry.name = "Player".to_string();
+        }
+        high_scores.insert(&table_key, self.entry.clone());
+        if let Err(e) = high_scores.save() {
+            log::error!("Failed to save high scores: {}", e);
+        }
+        self.saved_score = true;
+
+        let replay_prompt = self.replay_prompt(world);
+        let mut texts = world.write_storage::<UiText>();
+        if let Some(text) = texts.get_mut(self.small_text_entity.unwrap()) {
+            text.text = replay_prompt;
+        }
+        if let Some(text) = texts.get_mut(self.name_text_entity.unwrap()) {
+            text.text = format!("Saved as {}", self.entry.name);
+        }
+    }
+
+    fn update_name_text(&self, world: &World) {
+        if let Some(text) = world
+            .write_storage::<UiText>()
+            .get_mut(self.name_text_entity.unwrap())
+        {
+            text.text = self.name_text();
         }
     }
 }
@@ -27,6 +98,13 @@ impl SimpleState for GameOverState {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
 
+        if let Some(table_key) = &self.table_key {
+            let high_scores = HighScores::load();
+            if high_scores.qualifies(table_key, self.entry.score) {
+                self.high_scores = Some(high_scores);
+            }
+        }
+
         //Setup UI
         let font = world.read_resource::<Loader>().load(
             "Poppins-Black.ttf",
@@ -68,13 +146,47 @@ impl SimpleState for GameOverState {
             2000.,
             50.,
         );
+        let small_text = match self.high_scores {
+            Some(_) => format!(
+                "{} - Final Score {} - New High Score!",
+                self.reason_text, self.entry.score
+            ),
+            None => self.replay_prompt(world),
+        };
         self.small_text_entity = Some(
             world
                 .create_entity()
                 .with(small_text_transform)
                 .with(UiText::new(
                     font.clone(),
-                    self.reason_text.clone() + " - Press Any Key to Continue",
+                    small_text,
+                    [1.0, 1.0, 1.0, 1.0],
+                    30.,
+                ))
+                .build(),
+        );
+
+        let name_text_transform = UiTransform::new(
+            "Name Text".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            0.,
+            -100.,
+            1.,
+            2000.,
+            50.,
+        );
+        let name_text = match self.high_scores {
+            Some(_) => self.name_text(),
+            None => String::new(),
+        };
+        self.name_text_entity = Some(
+            world
+                .create_entity()
+                .with(name_text_transform)
+                .with(UiText::new(
+                    font.clone(),
+                    name_text,
                     [1.0, 1.0, 1.0, 1.0],
                     30.,
                 ))
@@ -82,14 +194,59 @@ impl SimpleState for GameOverState {
         );
     }
 
-    fn handle_event(&mut self, _data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
-        match event {
-            StateEvent::Input(input_event) => match input_event {
-                InputEvent::KeyPressed { .. } => {
+    fn handle_event(&mut self, data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+        if self.high_scores.is_none() {
+            if let StateEvent::Input(InputEvent::ActionPressed(action)) = event {
+                if action == "replay" {
+                    return Trans::Switch(Box::new(ReplayState::with_replay(self.replay.clone())));
+                }
+                if action == "confirm" || action == "back" {
+                    if self.saved_score {
+                        let table_key = self.table_key.clone().unwrap();
+                        return Trans::Switch(Box::new(HighScoresState::with_table(table_key)));
+                    }
                     return Trans::Switch(Box::new(MainMenuState::new()));
                 }
+            }
+            return Trans::None;
+        }
+
+        match event {
+            StateEvent::Window(Event::WindowEvent {
+                event: WindowEvent::ReceivedCharacter(c),
+                ..
+            }) if !c.is_control() && self.entry.name.chars().count() < MAX_NAME_LENGTH => {
+                self.entry.name.push(c);
+                self.update_name_text(data.world);
+            }
+            StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) => match key_code {
+                VirtualKeyCode::Back => {
+                    self.entry.name.pop();
+                    self.update_name_text(data.world);
+                }
+                //Return always saves, but when it's bound to confirm the action does the saving
+                VirtualKeyCode::Return => {
+                    let confirm_down = data
+                        .world
+                        .read_resource::<InputHandler<StringBindings>>()
+                        .action_is_down("confirm");
+                    if confirm_down != Some(true) {
+                        self.save_high_score(data.world);
+                    }
+                }
                 _ => {}
             },
+            StateEvent::Input(InputEvent::ActionPressed(action)) if action == "confirm" => {
+                //Other keys bound to confirm, like Space, are typing the name
+                let typing = data
+                    .world
+                    .read_resource::<InputHandler<StringBindings>>()
+                    .keys_that_are_down()
+                    .any(|key| key != VirtualKeyCode::Return);
+                if !typing {
+                    self.save_high_score(data.world);
+                }
+            }
             _ => {}
         }
 
@@ -103,5 +260,8 @@ impl SimpleState for GameOverState {
         data.world
             .delete_entity(self.small_text_entity.unwrap())
             .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.name_text_entity.unwrap())
+            .expect("Failed to Delete Entity");
     }
 }
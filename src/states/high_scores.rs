@@ -0,0 +1,237 @@
+use crate::game::HighScores;
+use crate::states::MainMenuState;
+use amethyst::{
+    assets::Loader,
+    core::ecs::prelude::*,
+    input::InputEvent,
+    prelude::*,
+    ui::{Anchor, TtfFormat, UiButton, UiButtonBuilder, UiEventType, UiText, UiTransform},
+};
+
+const ROW_COUNT: usize = 10;
+
+pub struct HighScoresState {
+    text_entity: Option<Entity>,
+    row_entities: Vec<Entity>,
+    table_button_entity: Option<UiButton>,
+    exit_button_entity: Option<UiButton>,
+    high_scores: HighScores,
+    keys: Vec<String>,
+    selected_table: usize,
+    start_table: Option<String>,
+}
+
+impl HighScoresState {
+    pub fn new() -> Self {
+        HighScoresState {
+            text_entity: None,
+            row_entities: Vec::new(),
+            table_button_entity: None,
+            exit_button_entity: None,
+            high_scores: HighScores::default(),
+            keys: Vec::new(),
+            selected_table: 0,
+            start_table: None,
+        }
+    }
+
+    // Opens on the table a run was just added to.
+    pub fn with_table(key: String) -> Self {
+        HighScoresState {
+            start_table: Some(key),
+            ..HighScoresState::new()
+        }
+    }
+
+    fn table_button_text(&self) -> String {
+        match self.keys.get(self.selected_table) {
+            Some(key) => format!("Table: {}", key),
+            None => "No High Scores Yet".to_string(),
+        }
+    }
+
+    fn row_text(&self, row: usize) -> String {
+        let table = match self.keys.get(self.selected_table) {
+            Some(key) => self.high_scores.table(key),
+            None => &[],
+        };
+        match table.get(row) {
+            Some(entry) => {
+                let seconds = entry.duration as u32;
+                format!(
+                    "{}. {} - {} Points - Length {} - {}:{:02} - {} - Seed {}",
+                    row + 1,
+                    entry.name,
+                    entry.score,
+                    entry.length,
+                    seconds / 60,
+                    seconds % 60,
+                    entry.date,
+                    entry.seed
+                )
+            }
+            None => String::new(),
+        }
+    }
+
+    fn refresh(&self, world: &World) {
+        let mut ui_texts = world.write_storage::<UiText>();
+        if let Some(text) = ui_texts.get_mut(self.table_button_entity.as_ref().unwrap().text_entity)
+        {
+            text.text = self.table_button_text();
+        }
+        for (row, entity) in self.row_entities.iter().enumerate() {
+            if let Some(text) = ui_texts.get_mut(*entity) {
+                text.text = self.row_text(row);
+            }
+        }
+    }
+}
+
+impl SimpleState for HighScoresState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        self.high_scores = HighScores::load();
+        self.keys = self.high_scores.keys();
+        self.selected_table = self
+            .start_table
+            .as_ref()
+            .and_then(|start| self.keys.iter().position(|key| key == start))
+            .unwrap_or(0);
+
+        //Setup UI
+        let font = world.read_resource::<Loader>().load(
+            "Poppins-Black.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        let text_transform = UiTransform::new(
+            "High Scores".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            0.,
+            500.,
+            1.,
+            2000.,
+            50.,
+        );
+        self.text_entity = Some(
+            world
+                .create_entity()
+                .with(text_transform)
+                .with(UiText::new(
+                    font.clone(),
+                    "High Scores".to_string(),
+                    [1.0, 1.0, 1.0, 1.0],
+                    50.,
+                ))
+                .build(),
+        );
+
+        for row in 0..ROW_COUNT {
+            let row_transform = UiTransform::new(
+                format!("High Score Row {}", row),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                300. - 50. * row as f32,
+                1.,
+                2000.,
+                40.,
+            );
+            self.row_entities.push(
+                world
+                    .create_entity()
+                    .with(row_transform)
+                    .with(UiText::new(
+                        font.clone(),
+                        self.row_text(row),
+                        [1.0, 1.0, 1.0, 1.0],
+                        25.,
+                    ))
+                    .build(),
+            );
+        }
+
+        self.table_button_entity = Some(
+            UiButtonBuilder::<(), u32>::new(self.table_button_text())
+                .with_size(400.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(0)
+                .with_position(0.0, 400.0)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1,
+        );
+
+        self.exit_button_entity = Some(
+            UiButtonBuilder::<(), u32>::new("Exit")
+                .with_size(200.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(1)
+                .with_position(0.0, -300.0)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1,
+        );
+    }
+
+    fn handle_event(&mut self, data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+        match event {
+            StateEvent::Ui(ui_event) => {
+                if ui_event.event_type == UiEventType::ClickStart {
+                    if ui_event.target == self.table_button_entity.as_ref().unwrap().image_entity
+                        && !self.keys.is_empty()
+                    {
+                        self.selected_table = (self.selected_table + 1) % self.keys.len();
+                        self.refresh(data.world);
+                    }
+                    if ui_event.target == self.exit_button_entity.as_ref().unwrap().image_entity {
+                        return Trans::Switch(Box::new(MainMenuState::new()));
+                    }
+                }
+            }
+            StateEvent::Input(InputEvent::ActionPressed(action)) => {
+                if action == "back" {
+                    return Trans::Switch(Box::new(MainMenuState::new()));
+                }
+            }
+            _ => {}
+        }
+
+        Trans::None
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        //Delete the text entity
+        data.world
+            .delete_entity(self.text_entity.unwrap())
+            .expect("Failed to Delete Entity");
+        for entity in self.row_entities.drain(..) {
+            data.world
+                .delete_entity(entity)
+                .expect("Failed to Delete Entity");
+        }
+        data.world
+            .delete_entity(self.table_button_entity.as_ref().unwrap().text_entity)
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.table_button_entity.as_ref().unwrap().image_entity)
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.exit_button_entity.as_ref().unwrap().text_entity)
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.exit_button_entity.as_ref().unwrap().image_entity)
+            .expect("Failed to Delete Entity");
+    }
+}
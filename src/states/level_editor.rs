@@ -0,0 +1,387 @@
+use amethyst::{
+    assets::Loader,
+    core::{ecs::prelude::*, math::Point2, transform::Transform},
+    input::{InputHandler, MouseButton, StringBindings},
+    prelude::*,
+    renderer::SpriteRender,
+    ui::{Anchor, TtfFormat, UiButton, UiButtonBuilder, UiEventType, UiText, UiTransform},
+    utils::application_root_dir,
+    window::ScreenDimensions,
+};
+
+use std::path::PathBuf;
+
+use super::primary::{create_map, initialise_camera, load_sprite_sheet};
+use crate::game::{
+    head_sprite, BoardConfig, Direction, GameMode, GameSeed, Level, Simulation, Snake, SnakeId,
+    WallMode,
+};
+use crate::states::{MainMenuState, PrimaryState};
+
+const CUSTOM_LEVEL_FILE: &str = "custom.ron";
+const BUTTON_ROW_HEIGHT: f32 = 70.0;
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum EditorTool {
+    Wall,
+    Food,
+    Spawn,
+}
+
+#[derive(Clone, Copy)]
+enum EditorAction {
+    Tool(EditorTool),
+    Rotate,
+    Save,
+    Load,
+    TestPlay,
+    Exit,
+}
+
+const EDITOR_BUTTONS: [(&str, EditorAction); 8] = [
+    ("Wall", EditorAction::Tool(EditorTool::Wall)),
+    ("Food", EditorAction::Tool(EditorTool::Food)),
+    ("Spawn", EditorAction::Tool(EditorTool::Spawn)),
+    ("Rotate", EditorAction::Rotate),
+    ("Save", EditorAction::Save),
+    ("Load", EditorAction::Load),
+    ("Test Play", EditorAction::TestPlay),
+    ("Exit", EditorAction::Exit),
+];
+
+pub struct LevelEditorState {
+    level: Level,
+    tool: EditorTool,
+    status: String,
+    camera_entity: Option<Entity>,
+    map_entity: Option<Entity>,
+    spawn_entity: Option<Entity>,
+    status_entity: Option<Entity>,
+    buttons: Vec<(EditorAction, UiButton)>,
+}
+
+impl LevelEditorState {
+    pub fn new() -> Self {
+        //Pick up where the last session left off, or start from an empty field
+        let level = Level::load(custom_level_path()).unwrap_or_else(|_| Level {
+            name: "Custom".to_string(),
+            ..Level::open(&BoardConfig::default())
+        });
+
+        LevelEditorState {
+            level,
+            tool: EditorTool::Wall,
+            status: String::new(),
+            camera_entity: None,
+            map_entity: None,
+            spawn_entity: None,
+            status_entity: None,
+            buttons: Vec::new(),
+        }
+    }
+
+    fn board(&self) -> BoardConfig {
+        BoardConfig {
+            width: self.level.width,
+            height: self.level.height,
+            wall_mode: WallMode::Solid,
+        }
+    }
+
+    fn status_text(&self) -> String {
+        let tool = match self.tool {
+            EditorTool::Wall => "Wall",
+            EditorTool::Food => "Food",
+            EditorTool::Spawn => "Spawn",
+        };
+        format!(
+            "Tool: {} - Left Click to Paint, Right Click to Erase {}",
+            tool, self.status
+        )
+    }
+
+    fn build_scene(&mut self, world: &mut World) {
+        let board = self.board();
+        self.camera_entity = Some(initialise_camera(world, &board));
+
+        let tile_sprite_sheet = load_sprite_sheet(world, "Tile.png", "Tile.ron");
+        self.map_entity = Some(create_map(world, &board, tile_sprite_sheet.clone()));
+
+        self.spawn_entity = Some(
+            world
+                .create_entity()
+                .with(SpriteRender {
+                    sprite_sheet: tile_sprite_sheet,
+                    sprite_number: head_sprite(self.level.heading),
+                })
+                .with(SnakeId(0))
+                .with(Transform::default())
+                .build(),
+        );
+
+        //Setup UI
+        let font = world.read_resource::<Loader>().load(
+            "Poppins-Black.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        let status_transform = UiTransform::new(
+            "Editor Status".to_string(),
+            Anchor::TopMiddle,
+            Anchor::TopMiddle,
+            0.,
+            -10.,
+            1.,
+            2000.,
+            30.,
+        );
+        self.status_entity = Some(
+            world
+                .create_entity()
+                .with(status_transform)
+                .with(UiText::new(
+                    font.clone(),
+                    self.status_text(),
+                    [1.0, 1.0, 1.0, 1.0],
+                    20.,
+                ))
+                .build(),
+        );
+
+        for (i, &(label, action)) in EDITOR_BUTTONS.iter().enumerate() {
+            let button = UiButtonBuilder::<(), u32>::new(label)
+                .with_size(140.0, 36.0)
+                .with_anchor(Anchor::BottomMiddle)
+                .with_font(font.clone())
+                .with_id(i as u32)
+                .with_position(-525.0 + 150.0 * i as f32, 30.0)
+                .with_font_size(20.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1;
+            self.buttons.push((action, button));
+        }
+
+        self.refresh(world);
+    }
+
+    fn clear_scene(&mut self, world: &mut World) {
+        world
+            .delete_entity(self.camera_entity.take().unwrap())
+            .expect("Failed to Delete Camera");
+        world
+            .delete_entity(self.map_entity.take().unwrap())
+            .expect("Failed to Delete Map");
+        world
+            .delete_entity(self.spawn_entity.take().unwrap())
+            .expect("Failed to Delete Entity");
+        world
+            .delete_entity(self.status_entity.take().unwrap())
+            .expect("Failed to Delete Entity");
+        for (_, button) in self.buttons.drain(..) {
+            world
+                .delete_entity(button.image_entity)
+                .expect("Failed to Delete Entity");
+            world
+                .delete_entity(button.text_entity)
+                .expect("Failed to Delete Entity");
+        }
+    }
+
+    // Pushes the edited level back into everything that draws it. The tile map draws
+    // whatever simulation and snakes are in the world, so those are rebuilt from the level
+    // with the spawn marker standing in as the snake.
+    fn refresh(&self, world: &mut World) {
+        let (simulation, mut snakes) = Simulation::from_level(
+            &self.level,
+            WallMode::Solid,
+            GameMode::default(),
+            GameSeed(0),
+        );
+        world.insert(simulation);
+        if let Some(snake) = snakes.pop() {
+            world
+                .write_storage::<Snake>()
+                .insert(self.spawn_entity.unwrap(), snake)
+                .expect("Failed to Insert Snake");
+        }
+
+        let (x, y) = self.board().cell_center(self.level.spawn_point());
+        if let Some(transform) = world
+            .write_storage::<Transform>()
+            .get_mut(self.spawn_entity.unwrap())
+        {
+            transform.set_translation_xyz(x, y, 1.0);
+        }
+        if let Some(sprite) = world
+            .write_storage::<SpriteRender>()
+            .get_mut(self.spawn_entity.unwrap())
+        {
+            sprite.sprite_number = head_sprite(self.level.heading);
+        }
+        if let Some(text) = world
+            .write_storage::<UiText>()
+            .get_mut(self.status_entity.unwrap())
+        {
+            text.text = self.status_text();
+        }
+    }
+
+    // Applies the current tool to a cell, returning whether the level changed.
+    fn paint(&mut self, cell: Point2<u32>, erase: bool) -> bool {
+        let point = (cell.x, cell.y);
+        if point == self.level.spawn {
+            return false;
+        }
+
+        let had_wall = self.level.walls.contains(&point);
+        let had_food = self.level.food.contains(&point);
+        self.level.walls.retain(|&p| p != point);
+        self.level.food.retain(|&p| p != point);
+
+        if erase {
+            return had_wall || had_food;
+        }
+        match self.tool {
+            EditorTool::Wall => {
+                self.level.walls.push(point);
+                !had_wall
+            }
+            EditorTool::Food => {
+                self.level.food.push(point);
+                !had_food
+            }
+            EditorTool::Spawn => {
+                self.level.spawn = point;
+                true
+            }
+        }
+    }
+
+    fn cell_under_mouse(&self, world: &World) -> Option<(Point2<u32>, bool)> {
+        let input = world.read_resource::<InputHandler<StringBindings>>();
+        let painting = input.mouse_button_is_down(MouseButton::Left);
+        let erasing = input.mouse_button_is_down(MouseButton::Right);
+        if !painting && !erasing {
+            return None;
+        }
+
+        let (mouse_x, mouse_y) = input.mouse_position()?;
+        let screen = world.read_resource::<ScreenDimensions>();
+        //Leave the button row along the bottom to the UI
+        if mouse_y > screen.height() - BUTTON_ROW_HEIGHT {
+            return None;
+        }
+
+        //The camera shows a square view of the board stretched over the window
+        let board = self.board();
+        let view_size = board.view_size();
+        let x = (mouse_x / screen.width() - 0.5) * view_size;
+        let y = (0.5 - mouse_y / screen.height()) * view_size;
+        board.cell_at(x, y).map(|cell| (cell, erasing))
+    }
+}
+
+impl SimpleState for LevelEditorState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.build_scene(data.world);
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.clear_scene(data.world);
+    }
+
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.status = String::new();
+        self.build_scene(data.world);
+    }
+
+    fn handle_event(&mut self, data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+        match event {
+            StateEvent::Ui(ui_event) => {
+                if ui_event.event_type == UiEventType::ClickStart {
+                    let action = self
+                        .buttons
+                        .iter()
+                        .find(|(_, button)| button.image_entity == ui_event.target)
+                        .map(|&(action, _)| action);
+
+                    match action {
+                        Some(EditorAction::Tool(tool)) => {
+                            self.tool = tool;
+                        }
+                        Some(EditorAction::Rotate) => {
+                            self.level.heading = match self.level.heading {
+                                Direction::Up => Direction::Right,
+                                Direction::Right => Direction::Down,
+                                Direction::Down => Direction::Left,
+                                Direction::Left => Direction::Up,
+                            };
+                        }
+                        Some(EditorAction::Save) => {
+                            self.status = match self.level.save(custom_level_path()) {
+                                Ok(()) => format!("(Saved {})", CUSTOM_LEVEL_FILE),
+                                Err(e) => format!("({})", e),
+                            };
+                        }
+                        Some(EditorAction::Load) => match Level::load(custom_level_path()) {
+                            Ok(level) => {
+                                //The board may have changed size, so rebuild the map
+                                self.level = level;
+                                self.status = format!("(Loaded {})", CUSTOM_LEVEL_FILE);
+                                self.clear_scene(data.world);
+                                self.build_scene(data.world);
+                            }
+                            Err(e) => {
+                                self.status = format!("({})", e);
+                            }
+                        },
+                        Some(EditorAction::TestPlay) => match self.level.validate() {
+                            Ok(()) => {
+                                return Trans::Push(Box::new(PrimaryState::test_play(
+                                    self.level.clone(),
+                                )));
+                            }
+                            Err(e) => {
+                                self.status = format!("({})", e);
+                            }
+                        },
+                        Some(EditorAction::Exit) => {
+                            return Trans::Switch(Box::new(MainMenuState::new()));
+                        }
+                        None => {}
+                    }
+                    self.refresh(data.world);
+                }
+            }
+            _ => {}
+        }
+
+        Trans::None
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some((cell, erase)) = self.cell_under_mouse(data.world) {
+            if self.paint(cell, erase) {
+                self.refresh(data.world);
+            }
+        }
+
+        Trans::None
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.clear_scene(data.world);
+    }
+}
+
+fn custom_level_path() -> PathBuf {
+    application_root_dir()
+        .unwrap_or_default()
+        .join("resources")
+        .join("levels")
+        .join(CUSTOM_LEVEL_FILE)
+}
@@ -1,7 +1,9 @@
-use crate::states::MainMenuState;
+use crate::game::SaveGame;
+use crate::states::{MainMenuState, PrimaryState};
 use amethyst::{
     assets::Loader,
     core::ecs::prelude::*,
+    input::InputEvent,
     prelude::*,
     ui::{Anchor, TtfFormat, UiButton, UiButtonBuilder, UiEventType, UiText, UiTransform},
 };
@@ -9,13 +11,19 @@ use amethyst::{
 pub struct LoadState {
     text_entity: Option<Entity>,
     exit_button_entity: Option<UiButton>,
+    save_button_entities: Vec<UiButton>,
+    saves: Vec<SaveGame>,
 }
 
+const MAX_SAVE_BUTTONS: usize = 8;
+
 impl LoadState {
     pub fn new() -> Self {
         LoadState {
             text_entity: None,
             exit_button_entity: None,
+            save_button_entities: Vec::new(),
+            saves: Vec::new(),
         }
     }
 }
@@ -24,6 +32,9 @@ impl SimpleState for LoadState {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
 
+        self.saves = SaveGame::list();
+        self.saves.truncate(MAX_SAVE_BUTTONS);
+
         //Setup UI
         let font = world.read_resource::<Loader>().load(
             "Poppins-Black.ttf",
@@ -48,7 +59,11 @@ impl SimpleState for LoadState {
                 .with(text_transform)
                 .with(UiText::new(
                     font.clone(),
-                    "Load State".to_string(),
+                    if self.saves.is_empty() {
+                        "No Saved Games".to_string()
+                    } else {
+                        "Load Game".to_string()
+                    },
                     [1.0, 1.0, 1.0, 1.0],
                     50.,
                 ))
@@ -61,13 +76,45 @@ impl SimpleState for LoadState {
                 .with_anchor(Anchor::Middle)
                 .with_font(font.clone())
                 .with_id(0)
-                .with_position(0.0, 200.0)
+                .with_position(0.0, -400.0)
                 .with_font_size(24.0f32)
                 .with_text_color([1.0f32, 1.0, 1.0, 1.0])
                 .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
                 .build_from_world(&world)
                 .1,
         );
+
+        for (i, save) in self.saves.iter().enumerate() {
+            let snakes = &save.snakes;
+            let label = match snakes.as_slice() {
+                [snake] => format!(
+                    "{} - {} - Score {} - Length {}",
+                    save.date,
+                    save.simulation.mode.name,
+                    snake.score.points,
+                    snake.snake.len()
+                ),
+                _ => format!(
+                    "{} - {} - {} Players",
+                    save.date,
+                    save.simulation.mode.name,
+                    snakes.len()
+                ),
+            };
+            self.save_button_entities.push(
+                UiButtonBuilder::<(), u32>::new(label)
+                    .with_size(800.0, 36.0)
+                    .with_anchor(Anchor::Middle)
+                    .with_font(font.clone())
+                    .with_id(i as u32 + 1)
+                    .with_position(0.0, 300.0 - 80.0 * i as f32)
+                    .with_font_size(24.0f32)
+                    .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                    .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                    .build_from_world(&world)
+                    .1,
+            );
+        }
     }
 
     fn handle_event(&mut self, _data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
@@ -77,6 +124,19 @@ impl SimpleState for LoadState {
                     if ui_event.target == self.exit_button_entity.as_ref().unwrap().image_entity {
                         return Trans::Switch(Box::new(MainMenuState::new()));
                     }
+                    if let Some(i) = self
+                        .save_button_entities
+                        .iter()
+                        .position(|button| button.image_entity == ui_event.target)
+                    {
+                        let save = self.saves.remove(i);
+                        return Trans::Switch(Box::new(PrimaryState::from_save(save)));
+                    }
+                }
+            }
+            StateEvent::Input(InputEvent::ActionPressed(action)) => {
+                if action == "back" {
+                    return Trans::Switch(Box::new(MainMenuState::new()));
                 }
             }
             _ => {}
@@ -95,5 +155,13 @@ impl SimpleState for LoadState {
         data.world
             .delete_entity(self.exit_button_entity.as_ref().unwrap().image_entity)
             .expect("Failed to Delete Entity");
+        for button in self.save_button_entities.drain(..) {
+            data.world
+                .delete_entity(button.text_entity)
+                .expect("Failed to Delete Entity");
+            data.world
+                .delete_entity(button.image_entity)
+                .expect("Failed to Delete Entity");
+        }
     }
 }
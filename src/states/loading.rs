@@ -1,14 +1,30 @@
-use crate::states::PrimaryState;
+use crate::game::{BotRegistry, Level, Setting, Settings, WallMode};
+use crate::states::{MainMenuState, PrimaryState};
 use amethyst::{
     assets::Loader,
     core::ecs::prelude::*,
+    input::InputEvent,
     prelude::*,
     ui::{Anchor, TtfFormat, UiButton, UiButtonBuilder, UiEventType, UiText, UiTransform},
+    utils::application_root_dir,
 };
 
+const MATCH_SETTINGS: [Setting; 4] = [
+    Setting::Players,
+    Setting::WinCondition,
+    Setting::Opponents,
+    Setting::Difficulty,
+];
+
 pub struct LoadingState {
     text_entity: Option<Entity>,
     exit_button_entity: Option<UiButton>,
+    walls_button_entity: Option<UiButton>,
+    level_button_entity: Option<UiButton>,
+    //Players, computer opponents and how they win, each cycled by clicking
+    match_buttons: Vec<(Setting, UiButton)>,
+    levels: Vec<Level>,
+    selected_level: usize,
 }
 
 impl LoadingState {
@@ -16,13 +32,55 @@ impl LoadingState {
         LoadingState {
             text_entity: None,
             exit_button_entity: None,
+            walls_button_entity: None,
+            level_button_entity: None,
+            match_buttons: Vec::new(),
+            levels: Vec::new(),
+            selected_level: 0,
         }
     }
 }
 
+impl LoadingState {
+    fn level_button_text(&self) -> String {
+        match self.selected_level {
+            0 => "Level: Open Field".to_string(),
+            n => format!("Level: {}", self.levels[n - 1].name),
+        }
+    }
+
+    // Starts the match on the chosen level, unless it hasn't room for every snake in it.
+    fn start_game(&self, world: &World) -> SimpleTrans {
+        //Choice 0 is the open field, shipped levels follow it
+        let level = match self.selected_level {
+            0 => return Trans::Switch(Box::new(PrimaryState::new())),
+            n => &self.levels[n - 1],
+        };
+        let settings = world.read_resource::<Settings>();
+        if let Err(e) = level.spawn_points(settings.players + settings.computers()) {
+            let mut ui_texts = world.write_storage::<UiText>();
+            if let Some(text) =
+                ui_texts.get_mut(self.level_button_entity.as_ref().unwrap().text_entity)
+            {
+                text.text = format!("{} ({})", self.level_button_text(), e);
+            }
+            return Trans::None;
+        }
+        Trans::Switch(Box::new(PrimaryState::with_level(level.clone())))
+    }
+}
+
 impl SimpleState for LoadingState {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
+        let settings = (*world.read_resource::<Settings>()).clone();
+        let wall_mode = settings.board.wall_mode;
+
+        let level_dir = application_root_dir()
+            .map(|root| root.join("resources").join("levels"))
+            .unwrap_or_default();
+        self.levels = Level::load_all(level_dir);
+        self.selected_level = 0;
 
         //Setup UI
         let font = world.read_resource::<Loader>().load(
@@ -68,17 +126,112 @@ impl SimpleState for LoadingState {
                 .build_from_world(&world)
                 .1,
         );
+
+        self.walls_button_entity = Some(
+            UiButtonBuilder::<(), u32>::new(wall_button_text(wall_mode))
+                .with_size(300.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(1)
+                .with_position(0.0, 100.0)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1,
+        );
+
+        self.level_button_entity = Some(
+            UiButtonBuilder::<(), u32>::new(self.level_button_text())
+                .with_size(400.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(2)
+                .with_position(0.0, 0.0)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1,
+        );
+
+        for (i, &setting) in MATCH_SETTINGS.iter().enumerate() {
+            let button = UiButtonBuilder::<(), u32>::new(settings.describe(setting))
+                .with_size(500.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(i as u32 + 3)
+                .with_position(0.0, -100.0 - 100.0 * i as f32)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1;
+            self.match_buttons.push((setting, button));
+        }
     }
 
-    fn handle_event(&mut self, _data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+    fn handle_event(&mut self, data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
         match event {
             StateEvent::Ui(ui_event) => {
                 if ui_event.event_type == UiEventType::ClickStart {
                     if ui_event.target == self.exit_button_entity.as_ref().unwrap().image_entity {
-                        return Trans::Switch(Box::new(PrimaryState::new()));
+                        return self.start_game(data.world);
+                    }
+                    if ui_event.target == self.walls_button_entity.as_ref().unwrap().image_entity {
+                        let mut settings = data.world.write_resource::<Settings>();
+                        settings.adjust(Setting::WallMode, true);
+                        if let Err(e) = settings.save() {
+                            log::error!("Failed to save settings: {}", e);
+                        }
+
+                        let mut ui_texts = data.world.write_storage::<UiText>();
+                        if let Some(text) =
+                            ui_texts.get_mut(self.walls_button_entity.as_ref().unwrap().text_entity)
+                        {
+                            text.text = wall_button_text(settings.board.wall_mode);
+                        }
+                    }
+                    if ui_event.target == self.level_button_entity.as_ref().unwrap().image_entity {
+                        self.selected_level = (self.selected_level + 1) % (self.levels.len() + 1);
+
+                        let mut ui_texts = data.world.write_storage::<UiText>();
+                        if let Some(text) =
+                            ui_texts.get_mut(self.level_button_entity.as_ref().unwrap().text_entity)
+                        {
+                            text.text = self.level_button_text();
+                        }
+                    }
+                    if let Some((setting, button)) = self
+                        .match_buttons
+                        .iter()
+                        .find(|(_, button)| button.image_entity == ui_event.target)
+                    {
+                        let mut settings = data.world.write_resource::<Settings>();
+                        match setting {
+                            Setting::Difficulty => settings
+                                .next_opponent(&data.world.read_resource::<BotRegistry>().names()),
+                            setting => settings.adjust(*setting, true),
+                        }
+                        if let Err(e) = settings.save() {
+                            log::error!("Failed to save settings: {}", e);
+                        }
+
+                        let mut ui_texts = data.world.write_storage::<UiText>();
+                        if let Some(text) = ui_texts.get_mut(button.text_entity) {
+                            text.text = settings.describe(*setting);
+                        }
                     }
                 }
             }
+            StateEvent::Input(InputEvent::ActionPressed(action)) => {
+                if action == "confirm" {
+                    return self.start_game(data.world);
+                }
+                if action == "back" {
+                    return Trans::Switch(Box::new(MainMenuState::new()));
+                }
+            }
             _ => {}
         }
 
@@ -95,5 +248,29 @@ impl SimpleState for LoadingState {
         data.world
             .delete_entity(self.exit_button_entity.as_ref().unwrap().image_entity)
             .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.walls_button_entity.as_ref().unwrap().text_entity)
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.walls_button_entity.as_ref().unwrap().image_entity)
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.level_button_entity.as_ref().unwrap().text_entity)
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.level_button_entity.as_ref().unwrap().image_entity)
+            .expect("Failed to Delete Entity");
+        for (_, button) in self.match_buttons.drain(..) {
+            data.world
+                .delete_entity(button.text_entity)
+                .expect("Failed to Delete Entity");
+            data.world
+                .delete_entity(button.image_entity)
+                .expect("Failed to Delete Entity");
+        }
     }
 }
+
+fn wall_button_text(wall_mode: WallMode) -> String {
+    format!("Walls: {}", wall_mode.name())
+}
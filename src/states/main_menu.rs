@@ -1,7 +1,11 @@
-use crate::states::{CreditsState, LoadState, LoadingState, SettingsState};
+use crate::states::{
+    CreditsState, HighScoresState, LevelEditorState, LoadState, LoadingState, ReplayState,
+    SettingsState,
+};
 use amethyst::{
     assets::Loader,
     core::ecs::prelude::*,
+    input::InputEvent,
     prelude::*,
     ui::{Anchor, TtfFormat, UiButton, UiButtonBuilder, UiEventType, UiText, UiTransform},
 };
@@ -12,6 +16,9 @@ pub struct MainMenuState {
     load_button_entity: Option<UiButton>,
     credits_button_entity: Option<UiButton>,
     settings_button_entity: Option<UiButton>,
+    editor_button_entity: Option<UiButton>,
+    high_scores_button_entity: Option<UiButton>,
+    replays_button_entity: Option<UiButton>,
 }
 
 impl MainMenuState {
@@ -22,6 +29,9 @@ impl MainMenuState {
             load_button_entity: None,
             credits_button_entity: None,
             settings_button_entity: None,
+            editor_button_entity: None,
+            high_scores_button_entity: None,
+            replays_button_entity: None,
         }
     }
 }
@@ -116,6 +126,48 @@ impl SimpleState for MainMenuState {
                 .build_from_world(&world)
                 .1,
         );
+
+        self.editor_button_entity = Some(
+            UiButtonBuilder::<(), u32>::new("Level Editor")
+                .with_size(200.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(4)
+                .with_position(0.0, -200.0)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1,
+        );
+
+        self.high_scores_button_entity = Some(
+            UiButtonBuilder::<(), u32>::new("High Scores")
+                .with_size(200.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(5)
+                .with_position(0.0, -300.0)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1,
+        );
+
+        self.replays_button_entity = Some(
+            UiButtonBuilder::<(), u32>::new("Replays")
+                .with_size(200.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(6)
+                .with_position(0.0, -400.0)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1,
+        );
     }
 
     fn handle_event(&mut self, _data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
@@ -136,6 +188,27 @@ impl SimpleState for MainMenuState {
                     {
                         return Trans::Switch(Box::new(CreditsState::new()));
                     }
+                    if ui_event.target == self.editor_button_entity.as_ref().unwrap().image_entity {
+                        return Trans::Switch(Box::new(LevelEditorState::new()));
+                    }
+                    if ui_event.target
+                        == self
+                            .high_scores_button_entity
+                            .as_ref()
+                            .unwrap()
+                            .image_entity
+                    {
+                        return Trans::Switch(Box::new(HighScoresState::new()));
+                    }
+                    if ui_event.target == self.replays_button_entity.as_ref().unwrap().image_entity
+                    {
+                        return Trans::Switch(Box::new(ReplayState::new()));
+                    }
+                }
+            }
+            StateEvent::Input(InputEvent::ActionPressed(action)) => {
+                if action == "confirm" {
+                    return Trans::Switch(Box::new(LoadingState::new()));
                 }
             }
             _ => {}
@@ -173,5 +246,28 @@ impl SimpleState for MainMenuState {
         data.world
             .delete_entity(self.settings_button_entity.as_ref().unwrap().text_entity)
             .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.editor_button_entity.as_ref().unwrap().image_entity)
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.editor_button_entity.as_ref().unwrap().text_entity)
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(
+                self.high_scores_button_entity
+                    .as_ref()
+                    .unwrap()
+                    .image_entity,
+            )
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.high_scores_button_entity.as_ref().unwrap().text_entity)
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.replays_button_entity.as_ref().unwrap().image_entity)
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.replays_button_entity.as_ref().unwrap().text_entity)
+            .expect("Failed to Delete Entity");
     }
 }
@@ -1,19 +1,29 @@
+mod bindings;
 mod credits;
 mod game_over;
+mod high_scores;
+mod level_editor;
 mod load;
 mod loading;
 mod main_menu;
 mod paused;
 mod primary;
+mod replay;
+mod results;
 mod settings;
 mod splash;
 
+pub use self::bindings::BindingsState;
 pub use self::credits::CreditsState;
 pub use self::game_over::GameOverState;
+pub use self::high_scores::HighScoresState;
+pub use self::level_editor::LevelEditorState;
 pub use self::load::LoadState;
 pub use self::loading::LoadingState;
 pub use self::main_menu::MainMenuState;
 pub use self::paused::PausedState;
-pub use self::primary::{GameState, PrimaryState};
+pub use self::primary::PrimaryState;
+pub use self::replay::ReplayState;
+pub use self::results::ResultsState;
 pub use self::settings::SettingsState;
 pub use self::splash::SplashState;
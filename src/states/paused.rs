@@ -1,14 +1,22 @@
 use amethyst::{
     assets::Loader,
     core::ecs::prelude::*,
-    input::{InputEvent, VirtualKeyCode},
+    input::{InputEvent, InputHandler, StringBindings},
     prelude::*,
     ui::{Anchor, TtfFormat, UiText, UiTransform},
 };
 
+use crate::game::{
+    action_key_name, in_player_order, world_snakes, Controller, GameSeed, SaveGame, Simulation,
+    SnakeId, TickTimer,
+};
+
+use super::primary::AutopilotUsed;
+
 pub struct PausedState {
     big_text_entity: Option<Entity>,
     small_text_entity: Option<Entity>,
+    save_text_entity: Option<Entity>,
 }
 
 impl PausedState {
@@ -16,6 +24,41 @@ impl PausedState {
         PausedState {
             small_text_entity: None,
             big_text_entity: None,
+            save_text_entity: None,
+        }
+    }
+
+    fn save_game(&self, world: &World) {
+        let mut save = SaveGame::new(
+            *world.read_resource::<GameSeed>(),
+            (*world.read_resource::<Simulation>()).clone(),
+            world_snakes(world),
+            in_player_order(
+                (
+                    &world.read_storage::<SnakeId>(),
+                    &world.read_storage::<Controller>(),
+                )
+                    .join(),
+            )
+            .into_iter()
+            .cloned()
+            .collect(),
+            (*world.read_resource::<TickTimer>()).clone(),
+        );
+        save.autopilot_used = world.read_resource::<AutopilotUsed>().0;
+        let message = match save.save() {
+            Ok(path) => {
+                log::info!("Saved game to {}", path.display());
+                "Game Saved".to_string()
+            }
+            Err(e) => format!("Save Failed: {}", e),
+        };
+
+        if let Some(text) = world
+            .write_storage::<UiText>()
+            .get_mut(self.save_text_entity.unwrap())
+        {
+            text.text = message;
         }
     }
 }
@@ -23,6 +66,13 @@ impl PausedState {
 impl SimpleState for PausedState {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
+        let (resume_key, save_key) = {
+            let input = world.read_resource::<InputHandler<StringBindings>>();
+            (
+                action_key_name(&input.bindings, "confirm"),
+                action_key_name(&input.bindings, "save"),
+            )
+        };
 
         //Setup UI
         let font = world.read_resource::<Loader>().load(
@@ -71,7 +121,30 @@ impl SimpleState for PausedState {
                 .with(small_text_transform)
                 .with(UiText::new(
                     font.clone(),
-                    "Press Spacebar to Start".to_string(),
+                    format!("Press {} to Start", resume_key),
+                    [1.0, 1.0, 1.0, 1.0],
+                    30.,
+                ))
+                .build(),
+        );
+
+        let save_text_transform = UiTransform::new(
+            "Save Text".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            0.,
+            -100.,
+            1.,
+            2000.,
+            50.,
+        );
+        self.save_text_entity = Some(
+            world
+                .create_entity()
+                .with(save_text_transform)
+                .with(UiText::new(
+                    font.clone(),
+                    format!("Press {} to Save", save_key),
                     [1.0, 1.0, 1.0, 1.0],
                     30.,
                 ))
@@ -79,13 +152,16 @@ impl SimpleState for PausedState {
         );
     }
 
-    fn handle_event(&mut self, _data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+    fn handle_event(&mut self, data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
         match event {
             StateEvent::Input(input_event) => match input_event {
-                InputEvent::KeyPressed { key_code, .. } => {
-                    if key_code == VirtualKeyCode::Space {
+                InputEvent::ActionPressed(action) => {
+                    if action == "confirm" || action == "pause" || action == "back" {
                         return Trans::Pop;
                     }
+                    if action == "save" {
+                        self.save_game(data.world);
+                    }
                 }
                 _ => {}
             },
@@ -102,5 +178,8 @@ impl SimpleState for PausedState {
         data.world
             .delete_entity(self.small_text_entity.unwrap())
             .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.save_text_entity.unwrap())
+            .expect("Failed to Delete Entity");
     }
 }
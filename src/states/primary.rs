@@ -6,29 +6,76 @@ use amethyst::{
     input::{InputEvent, VirtualKeyCode},
     prelude::*,
     renderer::{
-        sprite::SpriteSheetHandle, ActiveCamera, Camera, ImageFormat, SpriteSheet,
-        SpriteSheetFormat, Texture,
+        resources::Tint, sprite::SpriteSheetHandle, ActiveCamera, Camera, ImageFormat,
+        SpriteRender, SpriteSheet, SpriteSheetFormat, Texture,
     },
     tiles::{MortonEncoder, TileMap},
+    ui::{Anchor, TtfFormat, UiText, UiTransform},
+    utils::application_root_dir,
+    window::Window,
+    winit::dpi::LogicalSize,
 };
 
 use crate::game::{
-    defines::*, AudioHandles, DirectionChangeSystem, Food, MoveSystem, Snake, SnakeGameTile,
+    defines::*, player_tint, world_snakes, AudioHandles, BoardConfig, BotRegistry, Controller,
+    DirectionChangeSystem, GameMode, GameSeed, GameState, HeadRenderSystem, HighScoreEntry,
+    HighScores, Level, MoveSystem, Pilot, SaveGame, Settings, Simulation, Snake, SnakeGameTile,
+    SnakeId, TickTimer,
 };
-use crate::states::{GameOverState, PausedState};
+use crate::states::{GameOverState, PausedState, ResultsState};
 
-use std::ops::Deref;
+//Hands the player's snake to the autopilot, for demos and for testing long games
+const AUTOPILOT_KEY: VirtualKeyCode = VirtualKeyCode::F8;
+
+// Whether the autopilot has steered at any point in the game being played, since those
+// runs don't go on the high scores.
+pub(super) struct AutopilotUsed(pub bool);
 
 pub struct PrimaryState<'a, 'b> {
     dispatcher: Option<Dispatcher<'a, 'b>>,
+    camera_entity: Option<Entity>,
     map_entity: Option<Entity>,
+    snake_entities: Vec<Entity>,
+    hud_entity: Option<Entity>,
+    level: Option<Level>,
+    save: Option<SaveGame>,
+    test_play: bool,
 }
 
 impl<'a, 'b> PrimaryState<'a, 'b> {
     pub fn new() -> Self {
         PrimaryState {
             dispatcher: None,
+            camera_entity: None,
             map_entity: None,
+            snake_entities: Vec::new(),
+            hud_entity: None,
+            level: None,
+            save: None,
+            test_play: false,
+        }
+    }
+
+    pub fn with_level(level: Level) -> Self {
+        PrimaryState {
+            level: Some(level),
+            ..PrimaryState::new()
+        }
+    }
+
+    // Picks a saved game back up exactly where it was left.
+    pub fn from_save(save: SaveGame) -> Self {
+        PrimaryState {
+            save: Some(save),
+            ..PrimaryState::new()
+        }
+    }
+
+    // Plays a level from the editor, popping back to it when the snake dies.
+    pub fn test_play(level: Level) -> Self {
+        PrimaryState {
+            test_play: true,
+            ..PrimaryState::with_level(level)
         }
     }
 }
@@ -36,51 +83,118 @@ impl<'a, 'b> PrimaryState<'a, 'b> {
 impl<'a, 'b> SimpleState for PrimaryState<'a, 'b> {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
-        initialise_camera(world);
+        if !world.has_value::<GameMode>() {
+            world.insert(load_game_mode());
+        }
+        let settings = (*world.read_resource::<Settings>()).clone();
+        apply_window_settings(world, &settings);
+        let config = settings.board;
+        let mode = (*world.read_resource::<GameMode>()).clone();
+
+        let (seed, simulation, snakes, controllers, timer, autopilot_used) = match self.save.take()
+        {
+            Some(save) => (
+                save.seed,
+                save.simulation,
+                save.snakes,
+                save.controllers,
+                save.timer,
+                save.autopilot_used,
+            ),
+            None => {
+                let seed = GameSeed::random();
+                let level = self.level.clone().unwrap_or_else(|| Level::open(&config));
+                //The editor only tests levels with the one snake
+                let (players, computers) = if self.test_play {
+                    (1, 0)
+                } else {
+                    (settings.players, settings.computers())
+                };
+                let (simulation, snakes) = Simulation::multiplayer(
+                    &level,
+                    config.wall_mode,
+                    mode,
+                    seed,
+                    players + computers,
+                    settings.win_condition,
+                );
+                let opponent = match &settings.opponent_bot {
+                    Some(bot) => Controller::Bot(bot.clone()),
+                    None => Controller::Ai(settings.difficulty),
+                };
+                let controllers = (0..players)
+                    .map(Controller::Keyboard)
+                    .chain((0..computers).map(|_| opponent.clone()))
+                    .collect();
+                let timer = TickTimer::new(simulation.tick_period(&snakes));
+                (seed, simulation, snakes, controllers, timer, false)
+            }
+        };
+        let board = simulation.board;
+        self.camera_entity = Some(initialise_camera(world, &board));
 
         let tile_sprite_sheet = load_sprite_sheet(world, "Tile.png", "Tile.ron");
         let eating_noise = load_source_source(world, "EatingNoise.wav");
 
-        let map = TileMap::<SnakeGameTile, MortonEncoder>::new(
-            Vector3::new(GRID_SIZE, GRID_SIZE, 2),
-            Vector3::new(TILE_SIZE, TILE_SIZE, 0),
-            Some(tile_sprite_sheet),
+        self.map_entity = Some(create_map(world, &board, tile_sprite_sheet.clone()));
+
+        let hud = hud_text(&simulation, &snakes);
+        self.snake_entities = create_snakes(world, snakes, controllers, tile_sprite_sheet);
+
+        //Setup HUD
+        let font = world.read_resource::<Loader>().load(
+            "Poppins-Black.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
         );
 
-        self.map_entity = Some(
+        let hud_text_transform = UiTransform::new(
+            "HUD Text".to_string(),
+            Anchor::TopLeft,
+            Anchor::TopLeft,
+            20.,
+            -10.,
+            1.,
+            1000.,
+            30.,
+        );
+        self.hud_entity = Some(
             world
                 .create_entity()
-                .with(map)
-                .with(Transform::from(Vector3::new(
-                    TILE_SIZE as f32 / 2.0,
-                    TILE_SIZE as f32 / -2.0,
-                    0.0,
-                )))
+                .with(hud_text_transform)
+                .with(UiText::new(font, hud, [1.0, 1.0, 1.0, 1.0], 25.))
                 .build(),
         );
 
         world.insert(AudioHandles { eating_noise });
-        world.insert(Snake::default());
-        world.insert(GameState::default());
-        world.insert(Food::new());
+        world.insert(seed);
+        world.insert(timer);
+        world.insert(simulation);
+        world.insert(AutopilotUsed(autopilot_used));
 
         let mut dispatcher_builder = DispatcherBuilder::new();
-        dispatcher_builder.add(DirectionChangeSystem {}, "direction change", &[]);
-        dispatcher_builder.add(MoveSystem::default(), "move system", &[]);
+        dispatcher_builder.add(DirectionChangeSystem::default(), "direction change", &[]);
+        dispatcher_builder.add(MoveSystem::default(), "move system", &["direction change"]);
+        dispatcher_builder.add(HeadRenderSystem, "head render", &["move system"]);
         let mut dispatcher = dispatcher_builder.build();
         dispatcher.setup(world);
 
         self.dispatcher = Some(dispatcher);
     }
 
-    fn handle_event(&mut self, _data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+    fn handle_event(&mut self, data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
         match event {
             StateEvent::Input(input_event) => match input_event {
-                InputEvent::KeyPressed { key_code, .. } => {
-                    if key_code == VirtualKeyCode::P {
+                InputEvent::ActionPressed(action) => {
+                    if action == "pause" || action == "back" {
                         return Trans::Push(Box::new(PausedState::new()));
                     }
                 }
+                InputEvent::KeyPressed {
+                    key_code: AUTOPILOT_KEY,
+                    ..
+                } => toggle_autopilot(data.world),
                 _ => {}
             },
             _ => {}
@@ -94,29 +208,147 @@ impl<'a, 'b> SimpleState for PrimaryState<'a, 'b> {
             dispatcher.dispatch(&data.world);
         }
 
-        let state = data.world.fetch::<GameState>();
-        match state.deref() {
-            GameState::Playing => Trans::None,
-            GameState::HitWall => {
-                Trans::Switch(Box::new(GameOverState::new("You hit the wall".to_string())))
-            }
-            GameState::HitYourself => {
-                Trans::Switch(Box::new(GameOverState::new("You hit yourself".to_string())))
+        let simulation = data.world.fetch::<Simulation>();
+        let snakes = world_snakes(data.world);
+        let seed = *data.world.fetch::<GameSeed>();
+        if let Some(text) = data
+            .world
+            .write_storage::<UiText>()
+            .get_mut(self.hud_entity.unwrap())
+        {
+            text.text = hud_text(&simulation, &snakes);
+            let controllers = data.world.read_storage::<Controller>();
+            if controllers
+                .join()
+                .any(|controller| matches!(controller, Controller::Autopilot(_)))
+            {
+                text.text.push_str("   Autopilot");
             }
         }
+
+        if !simulation.is_over(&snakes) {
+            return Trans::None;
+        }
+        if self.test_play {
+            return Trans::Pop;
+        }
+
+        let replay = simulation.replay();
+        if let Err(e) = replay.save() {
+            log::error!("Failed to save replay: {}", e);
+        }
+
+        if snakes.len() > 1 {
+            return Trans::Switch(Box::new(ResultsState::new(&simulation, &snakes, replay)));
+        }
+
+        let reason = match snakes[0].state {
+            GameState::HitWall => "You hit the wall",
+            GameState::HitYourself => "You hit yourself",
+            GameState::BoardFull => "You filled the board!",
+            _ => "You crashed",
+        };
+        //Runs the autopilot played any part of don't count towards the high scores
+        let table_key = if data.world.read_resource::<AutopilotUsed>().0 {
+            None
+        } else {
+            Some(HighScores::table_key(&simulation.mode, &simulation.board))
+        };
+        Trans::Switch(Box::new(GameOverState::new(
+            reason.to_string(),
+            table_key,
+            HighScoreEntry::from_run(&simulation, &snakes[0], seed),
+            replay,
+        )))
     }
 
     fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         //Delete the text entity
+        data.world
+            .delete_entity(self.camera_entity.take().unwrap())
+            .expect("Failed to Delete Camera");
         data.world
             .delete_entity(self.map_entity.unwrap())
             .expect("Failed to Delete Map");
+        for snake in self.snake_entities.drain(..) {
+            data.world
+                .delete_entity(snake)
+                .expect("Failed to Delete Snake");
+        }
+        data.world
+            .delete_entity(self.hud_entity.unwrap())
+            .expect("Failed to Delete Entity");
 
         println!("End Primary State")
     }
 }
 
-fn load_sprite_sheet(world: &mut World, png_path: &str, ron_path: &str) -> SpriteSheetHandle {
+// Hands every player's snake over to the autopilot, or back to them. Computer snakes and
+// bots keep their pilots.
+fn toggle_autopilot(world: &World) {
+    let entities = world.entities();
+    let bots = world.read_resource::<BotRegistry>();
+    let mut controllers = world.write_storage::<Controller>();
+    let mut pilots = world.write_storage::<Pilot>();
+    let mut toggled = Vec::new();
+    for (entity, controller) in (&entities, &mut controllers).join() {
+        *controller = match *controller {
+            Controller::Keyboard(player) => Controller::Autopilot(player),
+            Controller::Autopilot(player) => Controller::Keyboard(player),
+            _ => continue,
+        };
+        toggled.push(entity);
+    }
+
+    let all: Vec<Controller> = controllers.join().cloned().collect();
+    if all
+        .iter()
+        .any(|controller| matches!(controller, Controller::Autopilot(_)))
+    {
+        world.write_resource::<AutopilotUsed>().0 = true;
+    }
+    for entity in toggled {
+        let controller = controllers.get(entity).unwrap();
+        if let Some(pilot) = controller.pilot(&all, &bots) {
+            pilots
+                .insert(entity, pilot)
+                .expect("Failed to Insert Pilot");
+        }
+    }
+}
+
+fn hud_text(simulation: &Simulation, snakes: &[Snake]) -> String {
+    let seconds = simulation.elapsed as u32;
+    let scores = match snakes {
+        [snake] => format!(
+            "Score {}   Length {}",
+            snake.score.points,
+            snake.snake.len()
+        ),
+        snakes => snakes
+            .iter()
+            .enumerate()
+            .map(|(i, snake)| {
+                let out = if snake.is_alive() { "" } else { " Out" };
+                format!("P{} {}{}", i + 1, snake.score.points, out)
+            })
+            .collect::<Vec<_>>()
+            .join("   "),
+    };
+    format!(
+        "{}   Time {}:{:02}   Speed {}",
+        scores,
+        seconds / 60,
+        seconds % 60,
+        simulation.speed_level(snakes)
+    )
+}
+
+pub(super) fn load_sprite_sheet(
+    world: &mut World,
+    png_path: &str,
+    ron_path: &str,
+) -> SpriteSheetHandle {
     let texture_handle = {
         let loader = world.read_resource::<Loader>();
         let texture_storage = world.read_resource::<AssetStorage<Texture>>();
@@ -132,38 +364,117 @@ fn load_sprite_sheet(world: &mut World, png_path: &str, ron_path: &str) -> Sprit
     )
 }
 
+fn load_game_mode() -> GameMode {
+    let path = application_root_dir()
+        .unwrap_or_default()
+        .join("config")
+        .join("game_mode.ron");
+    GameMode::load(&path).unwrap_or_else(|e| {
+        log::warn!("Using the default game mode: {}", e);
+        GameMode::default()
+    })
+}
+
+fn apply_window_settings(world: &World, settings: &Settings) {
+    if let Some(window) = world.try_fetch::<Window>() {
+        let (width, height) = settings.resolution;
+        window.set_inner_size(LogicalSize::new(f64::from(width), f64::from(height)));
+        if settings.fullscreen {
+            window.set_fullscreen(Some(window.get_current_monitor()));
+        } else {
+            window.set_fullscreen(None);
+        }
+    }
+}
+
 fn load_source_source(world: &mut World, src_path: &str) -> SourceHandle {
     let loader = world.read_resource::<Loader>();
     loader.load(src_path, WavFormat, (), &world.read_resource())
 }
 
-fn initialise_camera(world: &mut World) {
-    // Setup camera in a way that our screen covers whole arena and (0, 0) is in the bottom left.
+pub(super) fn create_map(
+    world: &mut World,
+    board: &BoardConfig,
+    sprite_sheet: SpriteSheetHandle,
+) -> Entity {
+    //The tiles draw the snakes, which may not have been set up by any system yet
+    world.register::<Snake>();
+    world.register::<SnakeId>();
+
+    let map = TileMap::<SnakeGameTile, MortonEncoder>::new(
+        Vector3::new(board.width, board.height, 2),
+        Vector3::new(TILE_SIZE, TILE_SIZE, 0),
+        Some(sprite_sheet),
+    );
+
+    world
+        .create_entity()
+        .with(map)
+        .with(Transform::from(Vector3::new(
+            TILE_SIZE as f32 / 2.0,
+            TILE_SIZE as f32 / -2.0,
+            0.0,
+        )))
+        .build()
+}
+
+pub(super) fn initialise_camera(world: &mut World, board: &BoardConfig) -> Entity {
+    // Setup camera in a way that our screen covers whole arena, keeping tiles square on
+    // boards that aren't.
+    let view_size = board.view_size();
     let mut transform = Transform::default();
     transform.set_translation_xyz(0.0, 0.0, 10.0);
 
     let cam = world
         .create_entity()
-        .with(Camera::standard_2d(
-            (TILE_SIZE * GRID_SIZE) as f32,
-            (TILE_SIZE * GRID_SIZE) as f32,
-        ))
+        .with(Camera::standard_2d(view_size, view_size))
         .with(transform)
         .build();
 
     let mut act_cam = world.write_resource::<ActiveCamera>();
     (*act_cam).entity = Some(cam);
+    cam
 }
 
-#[derive(PartialEq, Eq)]
-pub enum GameState {
-    Playing,
-    HitYourself,
-    HitWall,
-}
+// An entity for every snake, carrying what steers it and the sliding sprite for its head,
+// tinted to match its body.
+pub(super) fn create_snakes(
+    world: &mut World,
+    snakes: Vec<Snake>,
+    controllers: Vec<Controller>,
+    sprite_sheet: SpriteSheetHandle,
+) -> Vec<Entity> {
+    //Nothing that steers the snakes may have been set up by a system yet
+    world.register::<Controller>();
+    world.register::<Pilot>();
 
-impl Default for GameState {
-    fn default() -> Self {
-        GameState::Playing
-    }
+    let pilots: Vec<Option<Pilot>> = {
+        let bots = world.read_resource::<BotRegistry>();
+        controllers
+            .iter()
+            .map(|controller| controller.pilot(&controllers, &bots))
+            .collect()
+    };
+    snakes
+        .into_iter()
+        .zip(controllers.into_iter().zip(pilots))
+        .enumerate()
+        .map(|(player, (snake, (controller, pilot)))| {
+            let entity = world
+                .create_entity()
+                .with(snake)
+                .with(SnakeId(player))
+                .with(controller)
+                .with(SpriteRender {
+                    sprite_sheet: sprite_sheet.clone(),
+                    sprite_number: 9,
+                })
+                .with(Tint(player_tint(player)))
+                .with(Transform::default());
+            match pilot {
+                Some(pilot) => entity.with(pilot).build(),
+                None => entity.build(),
+            }
+        })
+        .collect()
 }
@@ -0,0 +1,328 @@
+use amethyst::{
+    assets::Loader,
+    core::{ecs::prelude::*, Time},
+    input::{InputEvent, InputHandler, StringBindings, VirtualKeyCode},
+    prelude::*,
+    ui::{Anchor, TtfFormat, UiText, UiTransform},
+};
+
+use std::path::PathBuf;
+
+use super::primary::{create_map, create_snakes, initialise_camera, load_sprite_sheet};
+use crate::game::{
+    action_key_name, in_player_order, Controller, HeadRenderSystem, Replay, Settings, Simulation,
+    Snake, SnakeId, TickTimer,
+};
+use crate::states::MainMenuState;
+
+const SEEK_TICKS: u32 = 100;
+const PLAYBACK_SPEEDS: [(VirtualKeyCode, f32); 4] = [
+    (VirtualKeyCode::Key1, 0.5),
+    (VirtualKeyCode::Key2, 1.0),
+    (VirtualKeyCode::Key3, 2.0),
+    (VirtualKeyCode::Key4, 4.0),
+];
+
+pub struct ReplayState<'a, 'b> {
+    dispatcher: Option<Dispatcher<'a, 'b>>,
+    replays: Vec<PathBuf>,
+    selected_replay: usize,
+    replay: Option<Replay>,
+    paused: bool,
+    speed: f32,
+    camera_entity: Option<Entity>,
+    map_entity: Option<Entity>,
+    snake_entities: Vec<Entity>,
+    hud_entity: Option<Entity>,
+    help_entity: Option<Entity>,
+}
+
+impl<'a, 'b> ReplayState<'a, 'b> {
+    // Plays back the recorded games, newest first.
+    pub fn new() -> Self {
+        ReplayState {
+            dispatcher: None,
+            replays: Vec::new(),
+            selected_replay: 0,
+            replay: None,
+            paused: false,
+            speed: 1.0,
+            camera_entity: None,
+            map_entity: None,
+            snake_entities: Vec::new(),
+            hud_entity: None,
+            help_entity: None,
+        }
+    }
+
+    pub fn with_replay(replay: Replay) -> Self {
+        ReplayState {
+            replay: Some(replay),
+            ..ReplayState::new()
+        }
+    }
+
+    fn build_scene(&mut self, world: &mut World) {
+        if let Some(replay) = self.replay.as_ref() {
+            let (simulation, snakes) = replay.start();
+            let board = simulation.board;
+            self.camera_entity = Some(initialise_camera(world, &board));
+
+            let tile_sprite_sheet = load_sprite_sheet(world, "Tile.png", "Tile.ron");
+            self.map_entity = Some(create_map(world, &board, tile_sprite_sheet.clone()));
+
+            world.insert(TickTimer::new(simulation.tick_period(&snakes)));
+            world.insert(simulation);
+            let controllers = vec![Controller::Replay; snakes.len()];
+            self.snake_entities = create_snakes(world, snakes, controllers, tile_sprite_sheet);
+        }
+
+        //Setup HUD
+        let font = world.read_resource::<Loader>().load(
+            "Poppins-Black.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        let hud_text_transform = UiTransform::new(
+            "Replay Text".to_string(),
+            Anchor::TopLeft,
+            Anchor::TopLeft,
+            20.,
+            -10.,
+            1.,
+            1000.,
+            30.,
+        );
+        self.hud_entity = Some(
+            world
+                .create_entity()
+                .with(hud_text_transform)
+                .with(UiText::new(
+                    font.clone(),
+                    String::new(),
+                    [1.0, 1.0, 1.0, 1.0],
+                    25.,
+                ))
+                .build(),
+        );
+
+        let help_text_transform = UiTransform::new(
+            "Replay Help".to_string(),
+            Anchor::BottomMiddle,
+            Anchor::BottomMiddle,
+            0.,
+            10.,
+            1.,
+            2000.,
+            30.,
+        );
+        let exit_key = action_key_name(
+            &world
+                .read_resource::<InputHandler<StringBindings>>()
+                .bindings,
+            "back",
+        );
+        self.help_entity = Some(
+            world
+                .create_entity()
+                .with(help_text_transform)
+                .with(UiText::new(
+                    font,
+                    format!("Space Pause - Period Step - Comma Step Back - Left/Right Seek - 1-4 Speed - Tab Next Replay - {} Exit", exit_key),
+                    [1.0, 1.0, 1.0, 1.0],
+                    20.,
+                ))
+                .build(),
+        );
+
+        self.refresh_hud(world);
+    }
+
+    fn clear_scene(&mut self, world: &mut World) {
+        for entity in [
+            self.camera_entity.take(),
+            self.map_entity.take(),
+            self.hud_entity.take(),
+            self.help_entity.take(),
+        ]
+        .iter()
+        .flatten()
+        {
+            world
+                .delete_entity(*entity)
+                .expect("Failed to Delete Entity");
+        }
+        for entity in self.snake_entities.drain(..) {
+            world
+                .delete_entity(entity)
+                .expect("Failed to Delete Entity");
+        }
+    }
+
+    fn refresh_hud(&self, world: &World) {
+        let text = match self.replay.as_ref() {
+            Some(replay) => {
+                let simulation = world.read_resource::<Simulation>();
+                format!(
+                    "Replay {}/{} - Tick {}/{} - {}x{}",
+                    self.selected_replay + 1,
+                    self.replays.len().max(1),
+                    simulation.ticks,
+                    replay.end_tick,
+                    self.speed,
+                    if self.paused { " - Paused" } else { "" }
+                )
+            }
+            None => "No Replays Recorded".to_string(),
+        };
+
+        if let Some(ui_text) = world
+            .write_storage::<UiText>()
+            .get_mut(self.hud_entity.unwrap())
+        {
+            ui_text.text = text;
+        }
+    }
+
+    fn seek(&self, world: &mut World, tick: u32) {
+        if let Some(replay) = self.replay.as_ref() {
+            let (simulation, snakes) = replay.seek(tick);
+            world.insert(TickTimer::new(simulation.tick_period(&snakes)));
+            world.insert(simulation);
+
+            //Put each snake entity back how it was at that tick
+            let ids = world.read_storage::<SnakeId>();
+            let mut storage = world.write_storage::<Snake>();
+            for (id, snake) in (&ids, &mut storage).join() {
+                *snake = snakes[id.0].clone();
+            }
+        }
+    }
+
+    // Runs however many ticks have built up at the current playback speed.
+    fn advance(&self, world: &mut World) {
+        let replay = match self.replay.as_ref() {
+            Some(replay) => replay,
+            None => return,
+        };
+
+        let delta_seconds = world.read_resource::<Time>().delta_seconds();
+        let mut simulation = world.write_resource::<Simulation>();
+        let mut timer = world.write_resource::<TickTimer>();
+        let ids = world.read_storage::<SnakeId>();
+        let mut storage = world.write_storage::<Snake>();
+        let mut snakes = in_player_order((&ids, &mut storage).join());
+        //Played back at the game speed the games are played at, then the playback speed
+        let game_speed = world.read_resource::<Settings>().game_speed;
+        timer.period = simulation.tick_period(&snakes) / (game_speed * self.speed);
+        for _ in 0..timer.advance(delta_seconds) {
+            if simulation.is_over(&snakes) {
+                break;
+            }
+            replay.step(&mut simulation, &mut snakes);
+        }
+    }
+
+    // Loads the selected replay, moving on past any that won't load so the one shown always
+    // matches its number.
+    fn load_selected(&mut self) {
+        self.replay = None;
+        for _ in 0..self.replays.len() {
+            let path = &self.replays[self.selected_replay];
+            match Replay::load(path) {
+                Ok(replay) => {
+                    self.replay = Some(replay);
+                    return;
+                }
+                Err(e) => log::error!("Could not load replay {}: {}", path.display(), e),
+            }
+            self.selected_replay = (self.selected_replay + 1) % self.replays.len();
+        }
+    }
+}
+
+impl<'a, 'b> SimpleState for ReplayState<'a, 'b> {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+
+        self.replays = Replay::list();
+        if self.replay.is_none() {
+            self.load_selected();
+        }
+        self.build_scene(world);
+
+        let mut dispatcher_builder = DispatcherBuilder::new();
+        dispatcher_builder.add(HeadRenderSystem, "head render", &[]);
+        let mut dispatcher = dispatcher_builder.build();
+        dispatcher.setup(world);
+
+        self.dispatcher = Some(dispatcher);
+    }
+
+    fn handle_event(&mut self, data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+        if let StateEvent::Input(InputEvent::ActionPressed(ref action)) = event {
+            if action == "back" {
+                return Trans::Switch(Box::new(MainMenuState::new()));
+            }
+        }
+
+        if let StateEvent::Input(InputEvent::KeyPressed { key_code, .. }) = event {
+            if self.replay.is_none() {
+                return Trans::None;
+            }
+
+            let tick = data.world.read_resource::<Simulation>().ticks;
+            match key_code {
+                VirtualKeyCode::Space => self.paused = !self.paused,
+                VirtualKeyCode::Period => {
+                    self.paused = true;
+                    self.seek(data.world, tick + 1);
+                }
+                VirtualKeyCode::Comma => {
+                    self.paused = true;
+                    self.seek(data.world, tick.saturating_sub(1));
+                }
+                VirtualKeyCode::Left => self.seek(data.world, tick.saturating_sub(SEEK_TICKS)),
+                VirtualKeyCode::Right => self.seek(data.world, tick + SEEK_TICKS),
+                VirtualKeyCode::Tab if !self.replays.is_empty() => {
+                    //The board may be a different size, so rebuild everything
+                    self.selected_replay = (self.selected_replay + 1) % self.replays.len();
+                    self.load_selected();
+                    self.paused = false;
+                    self.clear_scene(data.world);
+                    self.build_scene(data.world);
+                }
+                _ => {
+                    if let Some(&(_, speed)) =
+                        PLAYBACK_SPEEDS.iter().find(|&&(key, _)| key == key_code)
+                    {
+                        self.speed = speed;
+                    }
+                }
+            }
+            self.refresh_hud(data.world);
+        }
+
+        Trans::None
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if self.replay.is_some() {
+            if !self.paused {
+                self.advance(data.world);
+            }
+            if let Some(dispatcher) = self.dispatcher.as_mut() {
+                dispatcher.dispatch(&data.world);
+            }
+            self.refresh_hud(data.world);
+        }
+
+        Trans::None
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.clear_scene(data.world);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::game::{action_key_name, defines::*, GameState, Replay, Simulation, Snake};
+use crate::states::{MainMenuState, ReplayState};
+use amethyst::{
+    assets::Loader,
+    core::ecs::prelude::*,
+    input::{InputEvent, InputHandler, StringBindings},
+    prelude::*,
+    ui::{Anchor, TtfFormat, UiText, UiTransform},
+};
+
+// Ends a game with more than one snake, in place of the single player game over screen.
+pub struct ResultsState {
+    title: String,
+    rows: Vec<String>,
+    replay: Replay,
+    text_entities: Vec<Entity>,
+}
+
+impl ResultsState {
+    pub fn new(simulation: &Simulation, snakes: &[Snake], replay: Replay) -> Self {
+        let winners = simulation.winners(snakes);
+        let title = match winners.as_slice() {
+            [winner] => format!("Player {} Wins!", winner + 1),
+            _ => format!(
+                "Draw Between Players {}",
+                winners
+                    .iter()
+                    .map(|winner| (winner + 1).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+
+        let rows = snakes
+            .iter()
+            .enumerate()
+            .map(|(i, snake)| {
+                let fate = match snake.state {
+                    GameState::Playing => "Survived".to_string(),
+                    GameState::HitWall => "Hit the wall".to_string(),
+                    GameState::HitYourself => "Hit itself".to_string(),
+                    GameState::HitSnake(other) => format!("Hit Player {}", other + 1),
+                    GameState::HeadOn => "Crashed head-on".to_string(),
+                    GameState::BoardFull => "Filled the board".to_string(),
+                };
+                format!(
+                    "Player {} - Score {} - Length {} - {}",
+                    i + 1,
+                    snake.score.points,
+                    snake.snake.len(),
+                    fate
+                )
+            })
+            .collect();
+
+        ResultsState {
+            title,
+            rows,
+            replay,
+            text_entities: Vec::new(),
+        }
+    }
+}
+
+impl SimpleState for ResultsState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let world = data.world;
+        let prompt = {
+            let input = world.read_resource::<InputHandler<StringBindings>>();
+            format!(
+                "Press {} to Watch the Replay or {} to Continue",
+                action_key_name(&input.bindings, "replay"),
+                action_key_name(&input.bindings, "confirm")
+            )
+        };
+
+        //Setup UI
+        let font = world.read_resource::<Loader>().load(
+            "Poppins-Black.ttf",
+            TtfFormat,
+            (),
+            &world.read_resource(),
+        );
+
+        //The title, a row per player in their colour, then the prompt
+        let mut lines = vec![(self.title.clone(), [1.0, 1.0, 1.0, 1.0], 50.)];
+        for (i, row) in self.rows.iter().enumerate() {
+            lines.push((row.clone(), PLAYER_TINTS[i % MAX_PLAYERS], 30.));
+        }
+        lines.push((prompt, [1.0, 1.0, 1.0, 1.0], 30.));
+
+        for (i, (text, color, size)) in lines.into_iter().enumerate() {
+            let transform = UiTransform::new(
+                format!("Results Text {}", i),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                200. - 70. * i as f32,
+                1.,
+                2000.,
+                50.,
+            );
+            self.text_entities.push(
+                world
+                    .create_entity()
+                    .with(transform)
+                    .with(UiText::new(font.clone(), text, color, size))
+                    .build(),
+            );
+        }
+    }
+
+    fn handle_event(&mut self, _data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+        if let StateEvent::Input(InputEvent::ActionPressed(action)) = event {
+            if action == "replay" {
+                return Trans::Switch(Box::new(ReplayState::with_replay(self.replay.clone())));
+            }
+            if action == "confirm" || action == "back" {
+                return Trans::Switch(Box::new(MainMenuState::new()));
+            }
+        }
+
+        Trans::None
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        //Delete the text entities
+        for entity in self.text_entities.drain(..) {
+            data.world
+                .delete_entity(entity)
+                .expect("Failed to Delete Entity");
+        }
+    }
+}
@@ -1,14 +1,26 @@
-use crate::states::MainMenuState;
+use crate::game::{Setting, Settings, ALL_SETTINGS};
+use crate::states::{BindingsState, MainMenuState};
 use amethyst::{
     assets::Loader,
     core::ecs::prelude::*,
+    input::{InputEvent, InputHandler, MouseButton, StringBindings},
     prelude::*,
-    ui::{Anchor, TtfFormat, UiButton, UiButtonBuilder, UiEventType, UiText, UiTransform},
+    ui::{Anchor, TtfFormat, UiButton, UiButtonBuilder, UiEventType, UiImage, UiText, UiTransform},
+    window::ScreenDimensions,
 };
 
+const SLIDER_WIDTH: f32 = 480.0;
+
 pub struct SettingsState {
     text_entity: Option<Entity>,
     exit_button_entity: Option<UiButton>,
+    bindings_button_entity: Option<UiButton>,
+    //Each setting gets a label with a button either side to step it down or up
+    rows: Vec<(Setting, Entity, UiButton, UiButton)>,
+    //The settings that can also be dragged along a track, by row, with the track and its knob
+    sliders: Vec<(usize, UiButton, Entity)>,
+    //The slider being dragged while the mouse is held
+    dragging: Option<usize>,
 }
 
 impl SettingsState {
@@ -16,13 +28,56 @@ impl SettingsState {
         SettingsState {
             text_entity: None,
             exit_button_entity: None,
+            bindings_button_entity: None,
+            rows: Vec::new(),
+            sliders: Vec::new(),
+            dragging: None,
         }
     }
+
+    // Moves a setting and everything showing it: its label and any slider knob.
+    fn show_setting(&self, world: &World, row: usize) {
+        let (setting, label, _, _) = self.rows[row];
+        let settings = world.read_resource::<Settings>();
+        if let Some(text) = world.write_storage::<UiText>().get_mut(label) {
+            text.text = settings.describe(setting);
+        }
+        if let (Some((_, _, knob)), Some(position)) = (
+            self.sliders
+                .iter()
+                .find(|(slider_row, _, _)| *slider_row == row),
+            settings.slider_position(setting),
+        ) {
+            if let Some(transform) = world.write_storage::<UiTransform>().get_mut(*knob) {
+                transform.local_x = (position - 0.5) * SLIDER_WIDTH;
+            }
+        }
+    }
+
+    // Sets a slider's setting from where the mouse is along its track.
+    fn drag_slider(&self, world: &World, slider: usize) {
+        let (row, _, _) = self.sliders[slider];
+        let mouse_x = match world
+            .read_resource::<InputHandler<StringBindings>>()
+            .mouse_position()
+        {
+            Some((x, _)) => x,
+            None => return,
+        };
+        //The track is centred on the window
+        let width = world.read_resource::<ScreenDimensions>().width();
+        let position = (mouse_x - width / 2.0) / SLIDER_WIDTH + 0.5;
+        world
+            .write_resource::<Settings>()
+            .slide(self.rows[row].0, position);
+        self.show_setting(world, row);
+    }
 }
 
 impl SimpleState for SettingsState {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
+        let settings = (*world.read_resource::<Settings>()).clone();
 
         //Setup UI
         let font = world.read_resource::<Loader>().load(
@@ -48,20 +103,111 @@ impl SimpleState for SettingsState {
                 .with(text_transform)
                 .with(UiText::new(
                     font.clone(),
-                    "Settings State".to_string(),
+                    "Settings".to_string(),
                     [1.0, 1.0, 1.0, 1.0],
                     50.,
                 ))
                 .build(),
         );
 
+        for (i, &setting) in ALL_SETTINGS.iter().enumerate() {
+            let y = 380.0 - 80.0 * i as f32;
+
+            let label_transform = UiTransform::new(
+                format!("Setting {}", i),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                y,
+                1.,
+                500.,
+                36.,
+            );
+            let label = world
+                .create_entity()
+                .with(label_transform)
+                .with(UiText::new(
+                    font.clone(),
+                    settings.describe(setting),
+                    [1.0, 1.0, 1.0, 1.0],
+                    24.,
+                ))
+                .build();
+
+            let down_button = UiButtonBuilder::<(), u32>::new("-")
+                .with_size(60.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(2 * i as u32 + 1)
+                .with_position(-300.0, y)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1;
+
+            let up_button = UiButtonBuilder::<(), u32>::new("+")
+                .with_size(60.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(2 * i as u32 + 2)
+                .with_position(300.0, y)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1;
+
+            self.rows.push((setting, label, down_button, up_button));
+
+            if let Some(position) = settings.slider_position(setting) {
+                let track = UiButtonBuilder::<(), u32>::new("")
+                    .with_size(SLIDER_WIDTH, 10.0)
+                    .with_anchor(Anchor::Middle)
+                    .with_image(UiImage::SolidColor([0.4, 0.4, 0.4, 1.0]))
+                    .with_position(0.0, y - 30.0)
+                    .build_from_world(&world)
+                    .1;
+                let knob_transform = UiTransform::new(
+                    format!("Slider Knob {}", i),
+                    Anchor::Middle,
+                    Anchor::Middle,
+                    (position - 0.5) * SLIDER_WIDTH,
+                    y - 30.0,
+                    2.,
+                    12.,
+                    24.,
+                );
+                let knob = world
+                    .create_entity()
+                    .with(knob_transform)
+                    .with(UiImage::SolidColor([1.0, 1.0, 1.0, 1.0]))
+                    .build();
+                self.sliders.push((i, track, knob));
+            }
+        }
+
+        self.bindings_button_entity = Some(
+            UiButtonBuilder::<(), u32>::new("Key Bindings")
+                .with_size(200.0, 36.0)
+                .with_anchor(Anchor::Middle)
+                .with_font(font.clone())
+                .with_id(2 * ALL_SETTINGS.len() as u32 + 1)
+                .with_position(-150.0, -400.0)
+                .with_font_size(24.0f32)
+                .with_text_color([1.0f32, 1.0, 1.0, 1.0])
+                .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
+                .build_from_world(&world)
+                .1,
+        );
+
         self.exit_button_entity = Some(
-            UiButtonBuilder::<(), u32>::new("Exit")
+            UiButtonBuilder::<(), u32>::new("Save & Exit")
                 .with_size(200.0, 36.0)
                 .with_anchor(Anchor::Middle)
                 .with_font(font.clone())
                 .with_id(0)
-                .with_position(0.0, 200.0)
+                .with_position(150.0, -400.0)
                 .with_font_size(24.0f32)
                 .with_text_color([1.0f32, 1.0, 1.0, 1.0])
                 .with_hover_text_color([1.0f32, 0.0f32, 0.0f32, 1.0f32])
@@ -70,13 +216,49 @@ impl SimpleState for SettingsState {
         );
     }
 
-    fn handle_event(&mut self, _data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
+    fn handle_event(&mut self, data: StateData<GameData>, event: StateEvent) -> SimpleTrans {
         match event {
             StateEvent::Ui(ui_event) => {
                 if ui_event.event_type == UiEventType::ClickStart {
                     if ui_event.target == self.exit_button_entity.as_ref().unwrap().image_entity {
-                        return Trans::Switch(Box::new(MainMenuState::new()));
+                        return save_and_exit(data.world);
+                    }
+                    if ui_event.target == self.bindings_button_entity.as_ref().unwrap().image_entity
+                    {
+                        //Changes so far stay in the resource until Save & Exit
+                        return Trans::Switch(Box::new(BindingsState::new()));
+                    }
+
+                    let clicked =
+                        self.rows
+                            .iter()
+                            .enumerate()
+                            .find_map(|(row, (setting, _, down, up))| {
+                                if ui_event.target == down.image_entity {
+                                    Some((row, *setting, false))
+                                } else if ui_event.target == up.image_entity {
+                                    Some((row, *setting, true))
+                                } else {
+                                    None
+                                }
+                            });
+                    if let Some((row, setting, up)) = clicked {
+                        data.world.write_resource::<Settings>().adjust(setting, up);
+                        self.show_setting(data.world, row);
                     }
+
+                    self.dragging = self
+                        .sliders
+                        .iter()
+                        .position(|(_, track, _)| ui_event.target == track.image_entity);
+                    if let Some(slider) = self.dragging {
+                        self.drag_slider(data.world, slider);
+                    }
+                }
+            }
+            StateEvent::Input(InputEvent::ActionPressed(action)) => {
+                if action == "back" {
+                    return save_and_exit(data.world);
                 }
             }
             _ => {}
@@ -84,6 +266,22 @@ impl SimpleState for SettingsState {
 
         Trans::None
     }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        if let Some(slider) = self.dragging {
+            let held = data
+                .world
+                .read_resource::<InputHandler<StringBindings>>()
+                .mouse_button_is_down(MouseButton::Left);
+            if held {
+                self.drag_slider(data.world, slider);
+            } else {
+                self.dragging = None;
+            }
+        }
+        Trans::None
+    }
+
     fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         //Delete the text entity
         data.world
@@ -95,5 +293,42 @@ impl SimpleState for SettingsState {
         data.world
             .delete_entity(self.exit_button_entity.as_ref().unwrap().image_entity)
             .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.bindings_button_entity.as_ref().unwrap().text_entity)
+            .expect("Failed to Delete Entity");
+        data.world
+            .delete_entity(self.bindings_button_entity.as_ref().unwrap().image_entity)
+            .expect("Failed to Delete Entity");
+        for (_, track, knob) in self.sliders.drain(..) {
+            data.world
+                .delete_entity(knob)
+                .expect("Failed to Delete Entity");
+            data.world
+                .delete_entity(track.text_entity)
+                .expect("Failed to Delete Entity");
+            data.world
+                .delete_entity(track.image_entity)
+                .expect("Failed to Delete Entity");
+        }
+        for (_, label, down, up) in self.rows.drain(..) {
+            data.world
+                .delete_entity(label)
+                .expect("Failed to Delete Entity");
+            for button in [down, up].iter() {
+                data.world
+                    .delete_entity(button.text_entity)
+                    .expect("Failed to Delete Entity");
+                data.world
+                    .delete_entity(button.image_entity)
+                    .expect("Failed to Delete Entity");
+            }
+        }
+    }
+}
+
+fn save_and_exit(world: &World) -> SimpleTrans {
+    if let Err(e) = world.read_resource::<Settings>().save() {
+        log::error!("Failed to save settings: {}", e);
     }
+    Trans::Switch(Box::new(MainMenuState::new()))
 }
@@ -5,18 +5,26 @@ use amethyst::{
     ui::{Anchor, TtfFormat, UiText, UiTransform},
 };
 
+use crate::game::{BotRegistry, Settings};
 use crate::states::MainMenuState;
 
 pub struct SplashState {
     remaining_time: f32,
     text_entity: Option<Entity>,
+    bots: Option<BotRegistry>,
 }
 
 impl SplashState {
     pub fn new() -> Self {
+        SplashState::with_bots(BotRegistry::default())
+    }
+
+    // Starts the game with bots from other crates ready to play.
+    pub fn with_bots(bots: BotRegistry) -> Self {
         SplashState {
             remaining_time: 2.0,
             text_entity: None,
+            bots: Some(bots),
         }
     }
 }
@@ -24,6 +32,8 @@ impl SplashState {
 impl SimpleState for SplashState {
     fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
         let world = data.world;
+        world.insert(Settings::load());
+        world.insert(self.bots.take().unwrap_or_default());
 
         //Setup UI
         let font = world.read_resource::<Loader>().load(